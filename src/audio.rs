@@ -1,22 +1,320 @@
+pub mod audio {
 
-pub mod audio { 
+    use sdl2::audio::AudioCallback;
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+    use std::path::Path;
 
-    use sdl2::audio::AudioCallback; 
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
 
-    pub struct SquareWave { 
-        pub phase: f32, 
-        pub phase_inc: f32,
-        pub amplitude: f32,
+    pub const SAMPLE_RATE    : i32 = 44100;
+    pub const TIMER_FREQ     : u32 = 60;
+    const DEFAULT_FREQUENCY  : f32 = 261.0;
+    const DEFAULT_VOLUME     : f32 = 0.1;
+    const DEFAULT_ATTACK_MS  : f32 = 4.0;
+    const DEFAULT_RELEASE_MS : f32 = 8.0;
+    const NOISE_SEED         : u16 = 0x4000;
+    const WAV_BITS_PER_SAMPLE: u16 = 16;
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Waveform {
+        Square,
+        Triangle,
+        Sine,
+        Noise,
+    }
+
+    impl Waveform {
+        pub fn from_name(name: &str) -> Option<Waveform> {
+            match name {
+                "square" => Some(Waveform::Square),
+                "triangle" => Some(Waveform::Triangle),
+                "sine" => Some(Waveform::Sine),
+                "noise" => Some(Waveform::Noise),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct BeeperSettings {
+        pub waveform: Waveform,
+        pub frequency: f32,  // Hz
+        pub volume: f32,     // 0.0 - 1.0
+        pub attack_ms: f32,
+        pub release_ms: f32,
+    }
+
+    impl Default for BeeperSettings {
+        fn default() -> Self {
+            BeeperSettings {
+                waveform: Waveform::Square,
+                frequency: DEFAULT_FREQUENCY,
+                volume: DEFAULT_VOLUME,
+                attack_ms: DEFAULT_ATTACK_MS,
+                release_ms: DEFAULT_RELEASE_MS,
+            }
+        }
+    }
+
+    // tone generator for the sound timer: the gate follows sound_timer > 0 and the
+    // envelope ramps the output in and out so gating never produces a click
+    pub struct Beeper {
+        settings: BeeperSettings,
+        phase: f32,
+        phase_inc: f32,
+        attack_step: f32,
+        release_step: f32,
+        envelope: f32,
+        gate: bool,
+        muted: bool,
+        noise_register: u16,
+        noise_value: f32,
+    }
+
+    impl Beeper {
+
+        pub fn new(settings: BeeperSettings, sample_rate: i32) -> Self {
+            let samples_per_ms = sample_rate as f32 / 1_000.0;
+            Beeper {
+                settings,
+                phase: 0.0,
+                phase_inc: settings.frequency / sample_rate as f32,
+                attack_step: 1.0 / (settings.attack_ms * samples_per_ms).max(1.0),
+                release_step: 1.0 / (settings.release_ms * samples_per_ms).max(1.0),
+                envelope: 0.0,
+                gate: false,
+                muted: false,
+                noise_register: NOISE_SEED,
+                noise_value: 1.0,
+            }
+        }
+
+        pub fn set_gate(&mut self, gate: bool) {
+            self.gate = gate;
+        }
+
+        // returns the new mute state
+        pub fn toggle_mute(&mut self) -> bool {
+            self.muted = !self.muted;
+            self.muted
+        }
+
+        pub fn next_sample(&mut self) -> f32 {
+
+            // move envelope towards its target
+            if self.gate && !self.muted {
+                self.envelope = (self.envelope + self.attack_step).min(1.0);
+            } else {
+                self.envelope = (self.envelope - self.release_step).max(0.0);
+            }
+
+            // hold the phase while fully silent so each beep starts at the same point
+            if self.envelope == 0.0 {
+                return 0.0;
+            }
+
+            let sample = match self.settings.waveform {
+                Waveform::Square => if self.phase <= 0.5 { 1.0 } else { -1.0 },
+                Waveform::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+                Waveform::Sine => (self.phase * std::f32::consts::TAU).sin(),
+                Waveform::Noise => self.noise_value,
+            };
+
+            self.phase += self.phase_inc;
+            if self.phase >= 1.0 {
+                self.phase %= 1.0;
+                self.clock_noise();
+            }
+
+            sample * self.settings.volume * self.envelope
+        }
+
+        // 15-bit LFSR clocked once per period, so noise follows the configured pitch
+        fn clock_noise(&mut self) {
+            let feedback = (self.noise_register ^ (self.noise_register >> 1)) & 0x1;
+            self.noise_register = (self.noise_register >> 1) | (feedback << 14);
+            self.noise_value = if self.noise_register & 0x1 == 0 { 1.0 } else { -1.0 };
+        }
+    }
+
+    impl AudioCallback for Beeper {
+        type Channel = f32;
+
+        fn callback(&mut self, out: &mut [f32]) {
+            for x in out.iter_mut() {
+                *x = self.next_sample();
+            }
+        }
     }
 
-    impl AudioCallback for SquareWave { 
-        type Channel = f32; 
+    // renders the beeper offline, one timer tick at a time, so audio can be
+    // inspected without an audio device or archived as a WAV file
+    pub struct WavRecorder {
+        beeper: Beeper,
+        sample_rate: u32,
+        samples: Vec<i16>,
+        tick_remainder: u32,
+    }
+
+    impl WavRecorder {
+
+        pub fn new(settings: BeeperSettings, sample_rate: i32) -> Self {
+            WavRecorder {
+                beeper: Beeper::new(settings, sample_rate),
+                sample_rate: sample_rate as u32,
+                samples: Vec::new(),
+                tick_remainder: 0,
+            }
+        }
+
+        // synthesize one 60 Hz tick of audio, gating the beeper the same way the
+        // SDL device is gated
+        pub fn tick(&mut self, sound_timer: u8) {
+            let total = self.sample_rate + self.tick_remainder;
+            let sample_count = total / TIMER_FREQ;
+            self.tick_remainder = total % TIMER_FREQ;
+
+            self.beeper.set_gate(sound_timer > 0);
+            for _ in 0..sample_count {
+                let sample = self.beeper.next_sample();
+                self.samples.push((sample * i16::MAX as f32) as i16);
+            }
+        }
+
+        pub fn samples(&self) -> &[i16] {
+            &self.samples
+        }
+
+        // mono 16-bit PCM WAV file contents
+        pub fn to_wav_bytes(&self) -> Vec<u8> {
+            let block_align: u16 = WAV_BITS_PER_SAMPLE / 8;
+            let byte_rate: u32 = self.sample_rate * block_align as u32;
+            let data_len: u32 = (self.samples.len() * block_align as usize) as u32;
+
+            let mut bytes: Vec<u8> = Vec::with_capacity(44 + data_len as usize);
+            bytes.extend_from_slice(b"RIFF");
+            bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+            bytes.extend_from_slice(b"WAVE");
+
+            // format chunk
+            bytes.extend_from_slice(b"fmt ");
+            bytes.extend_from_slice(&16u32.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+            bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+            bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+            bytes.extend_from_slice(&byte_rate.to_le_bytes());
+            bytes.extend_from_slice(&block_align.to_le_bytes());
+            bytes.extend_from_slice(&WAV_BITS_PER_SAMPLE.to_le_bytes());
+
+            // data chunk
+            bytes.extend_from_slice(b"data");
+            bytes.extend_from_slice(&data_len.to_le_bytes());
+            for sample in self.samples.iter() {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+            bytes
+        }
+
+        pub fn write_wav(&self, path: &Path) -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writer.write_all(&self.to_wav_bytes())?;
+            writer.flush()
+        }
+    }
+}
 
-        fn callback(&mut self, out: &mut [f32]) { 
-            for x in out.iter_mut() { 
-                *x = if self.phase <= 0.5 { self.amplitude } else { -self.amplitude };
-                self.phase = (self.phase + self.phase_inc) % 1.0; 
+
+#[cfg(test)]
+mod tests {
+    use super::audio::{Beeper, BeeperSettings, Waveform, WavRecorder, SAMPLE_RATE, TIMER_FREQ};
+    use crate::cpu::cpu::CPU;
+
+    const AUDIO_ROM: &[u8] = include_bytes!("../assets/chip8-test-rom-with-audio.ch8");
+
+    #[test]
+    fn should_render_one_tick_of_samples_per_timer_update() {
+        let mut recorder = WavRecorder::new(BeeperSettings::default(), SAMPLE_RATE);
+
+        recorder.tick(0);
+        recorder.tick(1);
+
+        let tick_len = (SAMPLE_RATE as u32 / TIMER_FREQ) as usize;
+        assert!(recorder.samples().len() == tick_len * 2);
+        assert!(recorder.samples()[..tick_len].iter().all(|sample| *sample == 0));
+        assert!(recorder.samples()[tick_len..].iter().any(|sample| *sample != 0));
+    }
+
+    #[test]
+    fn should_ramp_in_and_out_without_clicks_when_gate_toggles() {
+        let settings = BeeperSettings { waveform: Waveform::Sine, volume: 1.0, ..BeeperSettings::default() };
+        let mut beeper = Beeper::new(settings, SAMPLE_RATE);
+
+        beeper.set_gate(true);
+        let attack: Vec<f32> = (0..SAMPLE_RATE / 10).map(|_| beeper.next_sample()).collect();
+        beeper.set_gate(false);
+        let release: Vec<f32> = (0..SAMPLE_RATE / 10).map(|_| beeper.next_sample()).collect();
+
+        let max_step = attack.iter().chain(release.iter())
+            .zip(attack.iter().chain(release.iter()).skip(1))
+            .map(|(a, b)| (b - a).abs())
+            .fold(0.0, f32::max);
+        assert!(attack[0].abs() < 0.01);
+        assert!(max_step < 0.1);
+        assert!(*release.last().unwrap() == 0.0);
+    }
+
+    #[test]
+    fn should_stay_silent_when_muted() {
+        let mut beeper = Beeper::new(BeeperSettings::default(), SAMPLE_RATE);
+        beeper.set_gate(true);
+
+        assert!(beeper.toggle_mute());
+        assert!((0..SAMPLE_RATE / 10).all(|_| beeper.next_sample() == 0.0));
+    }
+
+    #[test]
+    fn should_write_wav_header_matching_samples() {
+        let mut recorder = WavRecorder::new(BeeperSettings::default(), SAMPLE_RATE);
+        recorder.tick(1);
+
+        let bytes = recorder.to_wav_bytes();
+        let data_len = u32::from_le_bytes([bytes[40], bytes[41], bytes[42], bytes[43]]) as usize;
+
+        assert!(&bytes[0..4] == b"RIFF");
+        assert!(&bytes[8..12] == b"WAVE");
+        assert!(data_len == recorder.samples().len() * 2);
+        assert!(bytes.len() == 44 + data_len);
+    }
+
+    #[test]
+    fn should_beep_for_one_second_when_running_audio_test_rom() {
+        let mut cpu = CPU::new();
+        cpu.load_rom(AUDIO_ROM.to_vec()).unwrap();
+        let mut recorder = WavRecorder::new(BeeperSettings::default(), SAMPLE_RATE);
+
+        // run well past the point where the rom sets the sound timer to 60
+        for _ in 0..180 {
+            for _ in 0..10 {
+                cpu.step();
             }
+            cpu.update_timers();
+            recorder.tick(cpu.sound_timer);
         }
+
+        let tick_len = (SAMPLE_RATE as u32 / TIMER_FREQ) as usize;
+        let audible_ticks = recorder.samples()
+            .chunks(tick_len)
+            .filter(|tick| tick.iter().any(|sample| *sample != 0))
+            .count();
+        // 59 ticks of tone followed by the release tail
+        assert!(audible_ticks == 60);
+        assert!(recorder.samples().last() == Some(&0));
     }
-}
\ No newline at end of file
+}
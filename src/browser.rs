@@ -0,0 +1,316 @@
+pub mod browser {
+
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::rom::rom::is_rom_path;
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const MAX_RECENT_ROMS: usize = 8;
+    const HISTORY_FILE: &str = "chip8/history";
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub enum BrowserEntry {
+        Recent(PathBuf),
+        Parent,
+        Directory(PathBuf),
+        Rom(PathBuf),
+    }
+
+    impl BrowserEntry {
+        pub fn label(&self) -> String {
+            let name = |path: &Path| path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(path.display().to_string());
+            match self {
+                BrowserEntry::Recent(path) => format!("* {}  ({})", name(path), path.parent().map(|dir| dir.display().to_string()).unwrap_or_default()),
+                BrowserEntry::Parent => "../".to_string(),
+                BrowserEntry::Directory(path) => format!("{}/", name(path)),
+                BrowserEntry::Rom(path) => name(path),
+            }
+        }
+    }
+
+    // recently opened ROMs and the directory the browser was last in, stored one
+    // "dir = path" or "recent = path" line each, most recent ROM first
+    #[derive(Clone, Default, Debug)]
+    pub struct RomHistory {
+        pub last_dir: Option<PathBuf>,
+        pub recent: Vec<PathBuf>,
+    }
+
+    impl RomHistory {
+
+        pub fn parse(text: &str) -> RomHistory {
+            let mut history = RomHistory::default();
+            for line in text.lines() {
+                match line.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+                    Some(("dir", dir)) if !dir.is_empty() => history.last_dir = Some(PathBuf::from(dir)),
+                    Some(("recent", rom)) if !rom.is_empty() && history.recent.len() < MAX_RECENT_ROMS => {
+                        history.recent.push(PathBuf::from(rom));
+                    },
+                    _ => {},
+                }
+            }
+            history
+        }
+
+        // a missing or unreadable history file is an empty history
+        pub fn load(path: &Path) -> RomHistory {
+            fs::read_to_string(path).map(|text| RomHistory::parse(&text)).unwrap_or_default()
+        }
+
+        pub fn save(&self, path: &Path) -> Result<(), String> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+            }
+            fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+
+        pub fn to_text(&self) -> String {
+            let mut text = String::new();
+            if let Some(dir) = self.last_dir.as_ref() {
+                text += &format!("dir = {}\n", dir.display());
+            }
+            for rom in self.recent.iter() {
+                text += &format!("recent = {}\n", rom.display());
+            }
+            text
+        }
+
+        // move rom to the front of the recent list and remember its directory
+        pub fn add_recent(&mut self, rom: &Path) {
+            let rom = fs::canonicalize(rom).unwrap_or(rom.to_path_buf());
+            self.recent.retain(|recent| *recent != rom);
+            self.recent.insert(0, rom.clone());
+            self.recent.truncate(MAX_RECENT_ROMS);
+            self.last_dir = rom.parent().map(|dir| dir.to_path_buf());
+        }
+
+        // remember the directory the browser is in, true if it changed
+        pub fn visit_dir(&mut self, dir: &Path) -> bool {
+            let dir = fs::canonicalize(dir).unwrap_or(dir.to_path_buf());
+            if self.last_dir.as_ref() == Some(&dir) {
+                return false;
+            }
+            self.last_dir = Some(dir);
+            true
+        }
+    }
+
+    // lists the recent ROMs, then the subdirectories and ROMs of the current directory
+    pub struct RomBrowser {
+        pub dir: PathBuf,
+        pub entries: Vec<BrowserEntry>,
+        pub selected: usize,
+        pub scroll: usize,            // first entry shown
+        pub error: Option<String>,    // shown until the next action
+        recent: Vec<PathBuf>,
+    }
+
+    impl RomBrowser {
+
+        pub fn new(dir: &Path, recent: &[PathBuf]) -> RomBrowser {
+            let mut browser = RomBrowser {
+                dir: dir.to_path_buf(),
+                entries: Vec::new(),
+                selected: 0,
+                scroll: 0,
+                error: None,
+                recent: recent.iter().filter(|rom| rom.is_file()).cloned().collect(),
+            };
+            browser.refresh();
+            browser
+        }
+
+        // start in the last directory used, or the working directory
+        pub fn from_history(history: &RomHistory) -> RomBrowser {
+            let dir = history.last_dir.clone()
+                .filter(|dir| dir.is_dir())
+                .or(env::current_dir().ok())
+                .unwrap_or(PathBuf::from("."));
+            RomBrowser::new(&dir, &history.recent)
+        }
+
+        pub fn refresh(&mut self) {
+            let mut dirs: Vec<PathBuf> = Vec::new();
+            let mut roms: Vec<PathBuf> = Vec::new();
+            match fs::read_dir(&self.dir) {
+                Ok(read_dir) => {
+                    for path in read_dir.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+                        let hidden = path.file_name().map(|name| name.to_string_lossy().starts_with('.')).unwrap_or(false);
+                        if hidden { continue; }
+                        if path.is_dir() {
+                            dirs.push(path);
+                        } else if is_rom_path(&path) {
+                            roms.push(path);
+                        }
+                    }
+                },
+                Err(e) => self.error = Some(format!("{}: {}", self.dir.display(), e)),
+            }
+            let sort_key = |path: &PathBuf| path.file_name().map(|name| name.to_string_lossy().to_lowercase());
+            dirs.sort_by_key(sort_key);
+            roms.sort_by_key(sort_key);
+
+            self.entries = self.recent.iter().cloned().map(BrowserEntry::Recent).collect();
+            if self.dir.parent().is_some() {
+                self.entries.push(BrowserEntry::Parent);
+            }
+            self.entries.extend(dirs.into_iter().map(BrowserEntry::Directory));
+            self.entries.extend(roms.into_iter().map(BrowserEntry::Rom));
+            self.selected = 0;
+            self.scroll = 0;
+        }
+
+        pub fn move_selection(&mut self, delta: isize) {
+            let last = self.entries.len().saturating_sub(1) as isize;
+            self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+        }
+
+        // scroll so the selection is one of the rows shown
+        pub fn keep_visible(&mut self, rows: usize) {
+            if self.selected < self.scroll {
+                self.scroll = self.selected;
+            } else if rows > 0 && self.selected >= self.scroll + rows {
+                self.scroll = self.selected + 1 - rows;
+            }
+        }
+
+        pub fn go_up(&mut self) {
+            if let Some(parent) = self.dir.parent().map(|parent| parent.to_path_buf()) {
+                self.dir = parent;
+                self.refresh();
+            }
+        }
+
+        // open the selected entry, directories are entered and ROMs are returned
+        pub fn activate(&mut self) -> Option<PathBuf> {
+            self.error = None;
+            match self.entries.get(self.selected).cloned()? {
+                BrowserEntry::Recent(rom) | BrowserEntry::Rom(rom) => Some(rom),
+                BrowserEntry::Parent => {
+                    self.go_up();
+                    None
+                },
+                BrowserEntry::Directory(dir) => {
+                    self.dir = dir;
+                    self.refresh();
+                    None
+                },
+            }
+        }
+    }
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    // history file in the user's config directory
+    pub fn history_path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join(HISTORY_FILE))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::browser::{BrowserEntry, RomBrowser, RomHistory, MAX_RECENT_ROMS};
+
+    #[test]
+    fn should_round_trip_history_and_move_reopened_rom_to_front() {
+        let mut history = RomHistory::parse("dir = /roms\nrecent = /roms/a.ch8\nrecent = /roms/b.ch8\nnonsense\n");
+        assert!(history.last_dir == Some(PathBuf::from("/roms")));
+        assert!(history.recent.len() == 2);
+
+        history.add_recent(Path::new("/games/b.ch8"));
+        history.add_recent(Path::new("/roms/b.ch8"));
+        assert!(history.recent[0] == Path::new("/roms/b.ch8"));
+        assert!(history.recent.len() == 3);
+        assert!(history.last_dir == Some(PathBuf::from("/roms")));
+
+        let reparsed = RomHistory::parse(&history.to_text());
+        assert!(reparsed.recent == history.recent && reparsed.last_dir == history.last_dir);
+
+        for idx in 0..20 { history.add_recent(Path::new(&format!("/roms/{}.ch8", idx))); }
+        assert!(history.recent.len() == MAX_RECENT_ROMS);
+    }
+
+    #[test]
+    fn should_list_directories_before_roms_and_enter_them() {
+        let root = std::env::temp_dir().join(format!("chip8-browser-{}", std::process::id()));
+        fs::create_dir_all(root.join("games")).unwrap();
+        fs::write(root.join("pong.ch8"), [0x00, 0xE0]).unwrap();
+        fs::write(root.join("readme.md"), "notes").unwrap();
+        fs::write(root.join("games").join("ant.sc8"), [0x00, 0xE0]).unwrap();
+
+        let mut browser = RomBrowser::new(&root, &[]);
+        assert!(browser.entries == vec![
+            BrowserEntry::Parent,
+            BrowserEntry::Directory(root.join("games")),
+            BrowserEntry::Rom(root.join("pong.ch8")),
+        ]);
+
+        browser.move_selection(10);
+        assert!(browser.activate() == Some(root.join("pong.ch8")));
+
+        browser.move_selection(-1);
+        assert!(browser.activate().is_none());
+        assert!(browser.dir == root.join("games"));
+        assert!(browser.entries.last() == Some(&BrowserEntry::Rom(root.join("games").join("ant.sc8"))));
+
+        browser.go_up();
+        assert!(browser.dir == root);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn should_remember_directory_navigated_to_and_reopen_there() {
+        let root = std::env::temp_dir().join(format!("chip8-history-{}", std::process::id()));
+        fs::create_dir_all(root.join("games")).unwrap();
+        let history_file = root.join("history");
+
+        let mut history = RomHistory::default();
+        let mut browser = RomBrowser::new(&root, &[]);
+        assert!(history.visit_dir(&browser.dir));
+        assert!(!history.visit_dir(&browser.dir));
+
+        browser.selected = browser.entries.iter().position(|entry| *entry == BrowserEntry::Directory(root.join("games"))).unwrap();
+        browser.activate();
+        assert!(history.visit_dir(&browser.dir));
+        history.save(&history_file).unwrap();
+
+        let reopened = RomBrowser::from_history(&RomHistory::load(&history_file));
+        assert!(fs::canonicalize(&reopened.dir).unwrap() == fs::canonicalize(root.join("games")).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn should_scroll_to_keep_selection_visible() {
+        let mut browser = RomBrowser::new(Path::new("/"), &[]);
+        browser.entries = (0..20).map(|idx| BrowserEntry::Rom(PathBuf::from(format!("{}.ch8", idx)))).collect();
+
+        browser.move_selection(12);
+        browser.keep_visible(5);
+        assert!(browser.scroll == 8);
+
+        browser.move_selection(-10);
+        browser.keep_visible(5);
+        assert!(browser.scroll == 2);
+    }
+}
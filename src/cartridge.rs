@@ -0,0 +1,330 @@
+pub mod cartridge {
+
+    use gif::{ColorOutput, DecodeOptions};
+
+    use crate::cpu::cpu::{Platform, Quirks};
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const GIF_SIGNATURES: [&[u8]; 2] = [b"GIF87a", b"GIF89a"];
+
+    // Octo's memory size settings, anything else is plain CHIP-8
+    const SCHIP_MAX_SIZE: f64 = 3583.0;
+    const XOCHIP_MAX_SIZE: f64 = 65024.0;
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    // an Octo cartridge: a GIF whose frames hide the program's source and options in the
+    // low two bits of each pixel's palette index, four pixels per byte most significant
+    // bits first. the bytes are a 32-bit big endian length and then that many characters
+    // of JSON holding {"program": source, "options": {...}}
+    #[derive(Clone, Debug)]
+    pub struct Cartridge {
+        pub program: String,
+        options: Vec<(String, Json)>,
+    }
+
+    impl Cartridge {
+
+        pub fn is_cartridge(bytes: &[u8]) -> bool {
+            GIF_SIGNATURES.iter().any(|signature| bytes.starts_with(signature))
+        }
+
+        pub fn decode(bytes: &[u8]) -> Result<Cartridge, String> {
+            let payload = read_payload(bytes)?;
+            // Octo writes one character per byte
+            let text: String = payload.iter().map(|byte| *byte as char).collect();
+            let json = JsonParser { chars: text.chars().collect(), pos: 0 }.parse()
+                .map_err(|e| format!("cartridge data is not valid JSON: {}", e))?;
+
+            let program = match json.field("program") {
+                Some(Json::String(program)) => program.clone(),
+                _ => return Err("cartridge has no program".to_string()),
+            };
+            let options = match json.field("options") {
+                Some(Json::Object(options)) => options.clone(),
+                _ => Vec::new(),
+            };
+            Ok(Cartridge { program, options })
+        }
+
+        // the platform picked by Octo's memory size option, plain CHIP-8 leaves the choice to the user
+        pub fn platform(&self) -> Option<Platform> {
+            match self.option("maxSize") {
+                Some(Json::Number(size)) if *size >= XOCHIP_MAX_SIZE => Some(Platform::Xochip),
+                Some(Json::Number(size)) if *size == SCHIP_MAX_SIZE => Some(Platform::Schip),
+                _ => None,
+            }
+        }
+
+        // the Octo compatibility flags that match one of our quirks, Octo wraps sprites unless clipQuirks is set
+        pub fn quirks(&self) -> Quirks {
+            Quirks {
+                sprite_wrap: !self.flag("clipQuirks"),
+                display_wait: self.flag("vBlankQuirks"),
+                shift_in_place: self.flag("shiftQuirks"),
+                ..Quirks::default()
+            }
+        }
+
+        fn option(&self, name: &str) -> Option<&Json> {
+            self.options.iter().find(|(key, _)| key == name).map(|(_, value)| value)
+        }
+
+        fn flag(&self, name: &str) -> bool {
+            matches!(self.option(name), Some(Json::Bool(true)))
+        }
+    }
+
+    #[derive(Clone, PartialEq, Debug)]
+    enum Json {
+        Null,
+        Bool(bool),
+        Number(f64),
+        String(String),
+        Array(Vec<Json>),
+        Object(Vec<(String, Json)>),
+    }
+
+    impl Json {
+        fn field(&self, name: &str) -> Option<&Json> {
+            match self {
+                Json::Object(fields) => fields.iter().find(|(key, _)| key == name).map(|(_, value)| value),
+                _ => None,
+            }
+        }
+    }
+
+    // just enough JSON for cartridge payloads
+    struct JsonParser {
+        chars: Vec<char>,
+        pos: usize,
+    }
+
+    impl JsonParser {
+
+        fn parse(mut self) -> Result<Json, String> {
+            let value = self.value()?;
+            self.skip_whitespace();
+            if self.pos < self.chars.len() {
+                return Err(format!("unexpected {:?} after the value", self.chars[self.pos]));
+            }
+            Ok(value)
+        }
+
+        fn value(&mut self) -> Result<Json, String> {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => self.object(),
+                Some('[') => self.array(),
+                Some('"') => self.string().map(Json::String),
+                Some('t') => self.literal("true", Json::Bool(true)),
+                Some('f') => self.literal("false", Json::Bool(false)),
+                Some('n') => self.literal("null", Json::Null),
+                Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+                Some(c) => Err(format!("unexpected {:?}", c)),
+                None => Err("unexpected end of data".to_string()),
+            }
+        }
+
+        fn object(&mut self) -> Result<Json, String> {
+            let mut fields: Vec<(String, Json)> = Vec::new();
+            self.expect('{')?;
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Json::Object(fields));
+            }
+            loop {
+                self.skip_whitespace();
+                let key = self.string()?;
+                self.skip_whitespace();
+                self.expect(':')?;
+                fields.push((key, self.value()?));
+                self.skip_whitespace();
+                match self.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(Json::Object(fields)),
+                    _ => return Err("expected , or } in object".to_string()),
+                }
+            }
+        }
+
+        fn array(&mut self) -> Result<Json, String> {
+            let mut items: Vec<Json> = Vec::new();
+            self.expect('[')?;
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Json::Array(items));
+            }
+            loop {
+                items.push(self.value()?);
+                self.skip_whitespace();
+                match self.next() {
+                    Some(',') => continue,
+                    Some(']') => return Ok(Json::Array(items)),
+                    _ => return Err("expected , or ] in array".to_string()),
+                }
+            }
+        }
+
+        fn string(&mut self) -> Result<String, String> {
+            let mut text = String::new();
+            self.expect('"')?;
+            loop {
+                match self.next().ok_or("unterminated string")? {
+                    '"' => return Ok(text),
+                    '\\' => match self.next().ok_or("unterminated string")? {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'u' => {
+                            let digits: String = (0..4).filter_map(|_| self.next()).collect();
+                            let code = u32::from_str_radix(&digits, 16).map_err(|_| format!("invalid escape \\u{}", digits))?;
+                            text.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        },
+                        c => text.push(c),
+                    },
+                    c => text.push(c),
+                }
+            }
+        }
+
+        fn number(&mut self) -> Result<Json, String> {
+            let start = self.pos;
+            while self.peek().map(|c| c.is_ascii_digit() || "+-.eE".contains(c)).unwrap_or(false) {
+                self.pos += 1;
+            }
+            let text: String = self.chars[start..self.pos].iter().collect();
+            text.parse::<f64>().map(Json::Number).map_err(|_| format!("invalid number {:?}", text))
+        }
+
+        fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+            for expected in word.chars() {
+                if self.next() != Some(expected) {
+                    return Err(format!("expected {}", word));
+                }
+            }
+            Ok(value)
+        }
+
+        fn expect(&mut self, expected: char) -> Result<(), String> {
+            match self.next() {
+                Some(c) if c == expected => Ok(()),
+                Some(c) => Err(format!("expected {:?}, found {:?}", expected, c)),
+                None => Err(format!("expected {:?} at the end of data", expected)),
+            }
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+                self.pos += 1;
+            }
+        }
+
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.pos).copied()
+        }
+
+        fn next(&mut self) -> Option<char> {
+            let c = self.peek()?;
+            self.pos += 1;
+            Some(c)
+        }
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    // the length prefixed bytes packed into the palette indices of every frame
+    fn read_payload(bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let mut options = DecodeOptions::new();
+        options.set_color_output(ColorOutput::Indexed);
+        let mut decoder = options.read_info(bytes).map_err(|e| format!("invalid cartridge GIF: {}", e))?;
+
+        let mut data: Vec<u8> = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().map_err(|e| format!("invalid cartridge GIF: {}", e))? {
+            for pixels in frame.buffer.chunks_exact(4) {
+                data.push(pixels.iter().fold(0, |byte, pixel| (byte << 2) | (pixel & 0x3)));
+            }
+        }
+
+        if data.len() < 4 {
+            return Err("GIF is too small to be an Octo cartridge".to_string());
+        }
+        let len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        data.get(4..4 + len)
+            .map(|payload| payload.to_vec())
+            .ok_or(format!("cartridge says it holds {} bytes but its frames only hold {}", len, data.len() - 4))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::cartridge::Cartridge;
+    use crate::cpu::cpu::Platform;
+
+    const SMILEY_CARTRIDGE: &[u8] = include_bytes!("../assets/Smiley [Octo cartridge].gif");
+
+    // a one frame cartridge holding payload, packed into the palette indices like Octo does
+    fn cartridge_gif(payload: &str) -> Vec<u8> {
+        let mut data = (payload.len() as u32).to_be_bytes().to_vec();
+        data.extend(payload.bytes());
+        let pixels: Vec<u8> = (0..64 * 64)
+            .map(|idx| data.get(idx / 4).map(|byte| (byte >> (6 - 2 * (idx % 4))) & 0x3).unwrap_or(0))
+            .collect();
+        let mut gif: Vec<u8> = Vec::new();
+        let palette = [0x00, 0x00, 0x00, 0x55, 0x55, 0x55, 0xAA, 0xAA, 0xAA, 0xFF, 0xFF, 0xFF];
+        let mut encoder = gif::Encoder::new(&mut gif, 64, 64, &palette).unwrap();
+        encoder.write_frame(&gif::Frame::from_indexed_pixels(64, 64, pixels, None)).unwrap();
+        drop(encoder);
+        gif
+    }
+
+    #[test]
+    fn should_read_program_and_options_from_cartridge() {
+        let cartridge = Cartridge::decode(SMILEY_CARTRIDGE).unwrap();
+        let quirks = cartridge.quirks();
+
+        assert!(Cartridge::is_cartridge(SMILEY_CARTRIDGE));
+        assert!(cartridge.program.starts_with("# smiley"));
+        assert!(cartridge.program.contains(": main"));
+        assert!(cartridge.platform() == Some(Platform::Schip));
+        assert!(quirks.shift_in_place && !quirks.sprite_wrap && !quirks.display_wait);
+    }
+
+    #[test]
+    fn should_pick_platform_and_quirks_from_options() {
+        let xochip = Cartridge::decode(&cartridge_gif(
+            r##"{"program":": main\n\tclear","options":{"maxSize":65024,"clipQuirks":false,"vBlankQuirks":true,"fillColor":"#FFCC00"}}"##
+        )).unwrap();
+        let chip8 = Cartridge::decode(&cartridge_gif(r#"{"program":"","options":{"maxSize":3215,"clipQuirks":true}}"#)).unwrap();
+
+        assert!(xochip.program == ": main\n\tclear");
+        assert!(xochip.platform() == Some(Platform::Xochip));
+        assert!(xochip.quirks().sprite_wrap && xochip.quirks().display_wait && !xochip.quirks().shift_in_place);
+        assert!(chip8.platform().is_none());
+        assert!(!chip8.quirks().sprite_wrap);
+    }
+
+    #[test]
+    fn should_reject_gifs_that_are_not_cartridges() {
+        let mut truncated = cartridge_gif("{}");
+        truncated.truncate(truncated.len() / 2);
+
+        assert!(Cartridge::decode(&cartridge_gif("not json")).unwrap_err().contains("not valid JSON"));
+        assert!(Cartridge::decode(&cartridge_gif(r#"{"options":{}}"#)).unwrap_err().contains("no program"));
+        assert!(Cartridge::decode(&truncated).is_err());
+        assert!(Cartridge::decode(b"GIF89a and then nothing").is_err());
+        assert!(!Cartridge::is_cartridge(&[0x00, 0xE0]));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod cpu { 
 
     use rand::prelude::*; 
+    use std::collections::HashMap; 
 
     // -------------------
     // ---- CONSTANTS ----
@@ -8,31 +9,20 @@ pub mod cpu {
 
     pub const SCREEN_WIDTH : usize = 64; 
     pub const SCREEN_HEIGHT: usize = 32;
-    const MAX_STACK_SIZE: usize = 12;
-    const ROM_START_ADDR: usize = 0x200; 
-
-    const FONT_HEIGHT: usize = 5; 
-    const FONT_START: usize = 0x0000; 
-    const FONT_SPRITE_START_ADDRS: [usize; 16] = [
-        FONT_START,                     // FONT_0
-        FONT_START + FONT_HEIGHT * 0x1, // FONT_1
-        FONT_START + FONT_HEIGHT * 0x2, // FONT_2
-        FONT_START + FONT_HEIGHT * 0x3, // FONT_3
-        FONT_START + FONT_HEIGHT * 0x4, // FONT_4
-        FONT_START + FONT_HEIGHT * 0x5, // FONT_5
-        FONT_START + FONT_HEIGHT * 0x6, // FONT_6
-        FONT_START + FONT_HEIGHT * 0x7, // FONT_7
-        FONT_START + FONT_HEIGHT * 0x8, // FONT_8
-        FONT_START + FONT_HEIGHT * 0x9, // FONT_9
-        FONT_START + FONT_HEIGHT * 0xA, // FONT_A
-        FONT_START + FONT_HEIGHT * 0xB, // FONT_B
-        FONT_START + FONT_HEIGHT * 0xC, // FONT_C
-        FONT_START + FONT_HEIGHT * 0xD, // FONT_D
-        FONT_START + FONT_HEIGHT * 0xE, // FONT_E
-        FONT_START + FONT_HEIGHT * 0xF, // FONT_F
-    ]; 
-
-    const FONT_DATAS: [[u8; FONT_HEIGHT]; 16] = [ 
+    pub const DEFAULT_STACK_DEPTH: usize = 12; 
+    pub const DEFAULT_MEMORY_SIZE: usize = 0x1000; 
+    const UNBOUNDED_STACK_DEPTH: usize = 256; // cap for platforms without a fixed stack, stops runaway recursion
+    pub const ROM_START_ADDR: usize = 0x200; 
+    const LONG_LOAD_OPCODE: usize = 0xF000; // XO-CHIP "I = NNNN", the address is in the following word
+
+    pub const FONT_HEIGHT: usize = 5; 
+    pub const FONT_BYTES: usize = FONT_HEIGHT * 16; 
+    pub const DEFAULT_FONT_BASE: usize = 0x000; 
+
+    pub type Font = [[u8; FONT_HEIGHT]; 16]; 
+
+    // the common hex digits, as in the SCHIP small font
+    pub const FONT_DATAS: Font = [ 
         [0xF0, 0x90, 0x90, 0x90, 0xF0], // FONT_0
         [0x20, 0x60, 0x20, 0x20, 0x70], // FONT_1
         [0xF0, 0x10, 0xF0, 0x80, 0xF0], // FONT_2
@@ -51,10 +41,265 @@ pub mod cpu {
         [0xF0, 0x80, 0xF0, 0x80, 0x80], // FONT_F
     ];
 
+    const VIP_FONT: Font = [ 
+        [0xF0, 0x90, 0x90, 0x90, 0xF0], // FONT_0
+        [0x60, 0x20, 0x20, 0x20, 0x70], // FONT_1
+        [0xF0, 0x10, 0xF0, 0x80, 0xF0], // FONT_2
+        [0xF0, 0x10, 0xF0, 0x10, 0xF0], // FONT_3
+        [0xA0, 0xA0, 0xF0, 0x20, 0x20], // FONT_4
+        [0xF0, 0x80, 0xF0, 0x10, 0xF0], // FONT_5
+        [0xF0, 0x80, 0xF0, 0x90, 0xF0], // FONT_6
+        [0xF0, 0x10, 0x10, 0x10, 0x10], // FONT_7
+        [0xF0, 0x90, 0xF0, 0x90, 0xF0], // FONT_8
+        [0xF0, 0x90, 0xF0, 0x10, 0xF0], // FONT_9
+        [0xF0, 0x90, 0xF0, 0x90, 0x90], // FONT_A
+        [0xF0, 0x50, 0x70, 0x50, 0xF0], // FONT_B
+        [0xF0, 0x80, 0x80, 0x80, 0xF0], // FONT_C
+        [0xF0, 0x50, 0x50, 0x50, 0xF0], // FONT_D
+        [0xF0, 0x80, 0xF0, 0x80, 0xF0], // FONT_E
+        [0xF0, 0x80, 0xF0, 0x80, 0x80], // FONT_F
+    ];
+
+    const DREAM_6800_FONT: Font = [ 
+        [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], // FONT_0
+        [0x40, 0x40, 0x40, 0x40, 0x40], // FONT_1
+        [0xE0, 0x20, 0xE0, 0x80, 0xE0], // FONT_2
+        [0xE0, 0x20, 0xE0, 0x20, 0xE0], // FONT_3
+        [0x80, 0xA0, 0xA0, 0xE0, 0x20], // FONT_4
+        [0xE0, 0x80, 0xE0, 0x20, 0xE0], // FONT_5
+        [0xE0, 0x80, 0xE0, 0xA0, 0xE0], // FONT_6
+        [0xE0, 0x20, 0x20, 0x20, 0x20], // FONT_7
+        [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], // FONT_8
+        [0xE0, 0xA0, 0xE0, 0x20, 0xE0], // FONT_9
+        [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], // FONT_A
+        [0xC0, 0xA0, 0xE0, 0xA0, 0xC0], // FONT_B
+        [0xE0, 0x80, 0x80, 0x80, 0xE0], // FONT_C
+        [0xC0, 0xA0, 0xA0, 0xA0, 0xC0], // FONT_D
+        [0xE0, 0x80, 0xE0, 0x80, 0xE0], // FONT_E
+        [0xE0, 0x80, 0xC0, 0x80, 0x80], // FONT_F
+    ];
+
+    const ETI_660_FONT: Font = [ 
+        [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], // FONT_0
+        [0x20, 0x20, 0x20, 0x20, 0x20], // FONT_1
+        [0xE0, 0x20, 0xE0, 0x80, 0xE0], // FONT_2
+        [0xE0, 0x20, 0xE0, 0x20, 0xE0], // FONT_3
+        [0xA0, 0xA0, 0xE0, 0x20, 0x20], // FONT_4
+        [0xE0, 0x80, 0xE0, 0x20, 0xE0], // FONT_5
+        [0xE0, 0x80, 0xE0, 0xA0, 0xE0], // FONT_6
+        [0xE0, 0x20, 0x20, 0x20, 0x20], // FONT_7
+        [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], // FONT_8
+        [0xE0, 0xA0, 0xE0, 0x20, 0xE0], // FONT_9
+        [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], // FONT_A
+        [0x80, 0x80, 0xE0, 0xA0, 0xE0], // FONT_B
+        [0xE0, 0x80, 0x80, 0x80, 0xE0], // FONT_C
+        [0x20, 0x20, 0xE0, 0xA0, 0xE0], // FONT_D
+        [0xE0, 0x80, 0xE0, 0x80, 0xE0], // FONT_E
+        [0xE0, 0x80, 0xC0, 0x80, 0x80], // FONT_F
+    ];
+
     // -------------------------------------
     // ---- STRUCTS / ENUMS / HELPER FN ----
     // -------------------------------------
 
+    // behaviours that differ between CHIP-8 interpreters, the defaults follow the COSMAC VIP
+    #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+    pub struct Quirks { 
+        pub fx0a_immediate: bool, // FX0A takes a held key right away instead of waiting for press and release
+        pub sprite_wrap: bool,    // DXYN wraps sprites around the screen edges instead of clipping them
+        pub collision_rows: bool, // DXYN sets VF to the number of sprite rows that collided instead of 0 / 1
+        pub display_wait: bool,   // DXYN waits for the next 60 Hz vblank before drawing, one sprite per frame
+        pub shift_in_place: bool, // 8XY6/8XYE shift Vx like CHIP-48 instead of shifting Vy into Vx
+    }
+
+    impl Quirks { 
+
+        // enable quirks from a comma separated list of names
+        pub fn parse(list: &str) -> Result<Quirks, String> { 
+            let mut quirks = Quirks::default(); 
+            for name in list.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) { 
+                match name { 
+                    "fx0a-immediate" => quirks.fx0a_immediate = true, 
+                    "sprite-wrap" => quirks.sprite_wrap = true, 
+                    "collision-rows" => quirks.collision_rows = true, 
+                    "display-wait" => quirks.display_wait = true, 
+                    "shift-in-place" => quirks.shift_in_place = true, 
+                    _ => return Err(format!("unknown quirk: {}", name)), 
+                }
+            }
+            Ok(quirks)
+        }
+    }
+
+    // hex digit fonts of the machines CHIP-8 ran on
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum FontSet { 
+        Vip, 
+        Dream6800, 
+        Eti660, 
+        Schip, 
+    }
+
+    impl FontSet { 
+        pub fn from_name(name: &str) -> Option<FontSet> { 
+            match name { 
+                "vip" => Some(FontSet::Vip), 
+                "dream6800" => Some(FontSet::Dream6800), 
+                "eti660" => Some(FontSet::Eti660), 
+                "schip" => Some(FontSet::Schip), 
+                _ => None, 
+            }
+        }
+
+        pub fn glyphs(&self) -> &'static Font { 
+            match self { 
+                FontSet::Vip => &VIP_FONT, 
+                FontSet::Dream6800 => &DREAM_6800_FONT, 
+                FontSet::Eti660 => &ETI_660_FONT, 
+                FontSet::Schip => &FONT_DATAS, 
+            }
+        }
+    }
+
+    // a custom font file holds the 16 glyphs back to back, 5 bytes each
+    pub fn parse_font(bytes: &[u8]) -> Result<Font, String> { 
+        if bytes.len() != FONT_BYTES { 
+            return Err(format!("expected a font of {} bytes, got {}", FONT_BYTES, bytes.len())); 
+        }
+        let mut font: Font = [[0; FONT_HEIGHT]; 16]; 
+        for (glyph, chunk) in font.iter_mut().zip(bytes.chunks(FONT_HEIGHT)) { 
+            glyph.copy_from_slice(chunk); 
+        }
+        Ok(font)
+    }
+
+    // machines a ROM can be written for, each brings its own defaults
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Platform { 
+        Vip, 
+        Dream6800, 
+        Eti660, 
+        Chip48, 
+        Schip, 
+        Xochip, 
+    }
+
+    impl Platform { 
+        pub fn from_name(name: &str) -> Option<Platform> { 
+            match name { 
+                "vip" => Some(Platform::Vip), 
+                "dream6800" => Some(Platform::Dream6800), 
+                "eti660" => Some(Platform::Eti660), 
+                "chip48" => Some(Platform::Chip48), 
+                "schip" => Some(Platform::Schip), 
+                "xochip" => Some(Platform::Xochip), 
+                _ => None, 
+            }
+        }
+
+        pub fn font_set(&self) -> FontSet { 
+            match self { 
+                Platform::Vip => FontSet::Vip, 
+                Platform::Dream6800 => FontSet::Dream6800, 
+                Platform::Eti660 => FontSet::Eti660, 
+                Platform::Chip48 | Platform::Schip | Platform::Xochip => FontSet::Schip, 
+            }
+        }
+
+        // quirks of the platform's interpreter, the HP48 ones shift Vx in place
+        pub fn quirks(&self) -> Quirks { 
+            match self { 
+                Platform::Chip48 | Platform::Schip => Quirks { shift_in_place: true, ..Quirks::default() }, 
+                _ => Quirks::default(), 
+            }
+        }
+
+        // where the interpreter keeps the font, SCHIP era interpreters use 0x050
+        pub fn font_base(&self) -> usize { 
+            match self { 
+                Platform::Schip => 0x050, 
+                _ => DEFAULT_FONT_BASE, 
+            }
+        }
+
+        // return addresses the stack holds, the VIP reserves room for 12 and the HP48
+        // interpreters for 16, XO-CHIP does not fix a depth
+        pub fn stack_depth(&self) -> usize { 
+            match self { 
+                Platform::Vip | Platform::Dream6800 | Platform::Eti660 => DEFAULT_STACK_DEPTH, 
+                Platform::Chip48 | Platform::Schip => 16, 
+                Platform::Xochip => UNBOUNDED_STACK_DEPTH, 
+            }
+        }
+
+        pub fn memory_size(&self) -> usize { 
+            match self { 
+                Platform::Xochip => 0x10000, 
+                _ => DEFAULT_MEMORY_SIZE, 
+            }
+        }
+    }
+
+    // what 0NNN does, on the VIP it calls the RCA 1802 machine code routine at NNN
+    #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+    pub enum MachineCodeMode { 
+        Ignore,   // skip the call and carry on
+        #[default]
+        Halt,     // stop the CPU and report the call
+        Dispatch, // run the routine registered for NNN, halting when there is none
+    }
+
+    impl MachineCodeMode { 
+        pub fn from_name(name: &str) -> Option<MachineCodeMode> { 
+            match name { 
+                "ignore" => Some(MachineCodeMode::Ignore), 
+                "halt" => Some(MachineCodeMode::Halt), 
+                "dispatch" => Some(MachineCodeMode::Dispatch), 
+                _ => None, 
+            }
+        }
+    }
+
+    // stand-in for a machine code routine, runs in place of the 1802 code and returns to the next instruction
+    pub type MachineRoutine = fn(&mut CPU); 
+
+    // routines hybrid ROMs commonly carry their own 1802 code for, by name
+    pub fn builtin_routine(name: &str) -> Option<MachineRoutine> { 
+        match name { 
+            "nop" => Some(|_| {}), 
+            "cls" => Some(|cpu| cpu.pixels = [false; SCREEN_WIDTH * SCREEN_HEIGHT]), 
+            _ => None, 
+        }
+    }
+
+    // parse "addr=name" into the address of a ROM's machine code routine and the builtin to run for it
+    pub fn parse_routine(text: &str) -> Result<(usize, MachineRoutine), String> { 
+        let (addr, name) = text.split_once('=').ok_or(format!("expected addr=name, got {}", text))?; 
+        let digits = addr.trim().trim_start_matches("0x").trim_start_matches('$'); 
+        let addr = usize::from_str_radix(digits, 16)
+            .ok()
+            .filter(|addr| *addr <= 0xFFF)
+            .ok_or(format!("invalid routine address: {}", addr))?; 
+        let routine = builtin_routine(name.trim()).ok_or(format!("unknown routine: {}", name))?; 
+        Ok((addr, routine))
+    }
+
+    // progress of an FX0A instruction waiting for a key to be pressed and released
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum KeyWait { 
+        Idle, 
+        WaitingForPress, 
+        WaitingForRelease(usize), 
+        Released(usize), 
+    }
+
+    // key the program last asked about, through EX9E / EXA1 or by waiting with FX0A
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum KeyQuery { 
+        Key(usize), 
+        AnyKey, 
+    }
+
     #[derive(PartialEq, Eq, Hash)]
     pub enum Chip8Input { 
         Num0,  
@@ -96,43 +341,71 @@ pub mod cpu {
         }
     }
 
-    fn get_fresh_memory_with_font_data() -> [u8; 0x1000] { 
-        let mut memory: [u8; 0x1000] = [0; 0x1000];
-        for i in 0..0xF { 
-            for j in 0..FONT_HEIGHT { 
-                memory[FONT_SPRITE_START_ADDRS[i]+j] = FONT_DATAS[i][j];
-            }
+    fn get_fresh_memory_with_font_data(memory_size: usize) -> Vec<u8> { 
+        let mut memory: Vec<u8> = vec![0; memory_size];
+        for (i, glyph) in FONT_DATAS.iter().enumerate() { 
+            let glyph_addr = DEFAULT_FONT_BASE + FONT_HEIGHT * i; 
+            memory[glyph_addr..glyph_addr + FONT_HEIGHT].copy_from_slice(glyph); 
         }
         memory
     }
 
     pub struct CPU { 
         pub pixels: [bool; SCREEN_WIDTH * SCREEN_HEIGHT], 
-        pub memory: [u8; 0x1000], 
+        pub memory: Vec<u8>, 
         pub registers: [u8; 16], 
         pub reg_i: usize, 
-        pub stack: [usize; MAX_STACK_SIZE], 
-        delay_timer: u8, 
+        pub stack: Vec<usize>, 
+        pub delay_timer: u8, 
         pub sound_timer: u8,
         pub pc: usize, // PROGRAM COUNTER
         pub sp: usize, // STACK POINTER
+        pub last_key_query: Option<KeyQuery>, 
+        pub quirks: Quirks, 
+        pub machine_code: MachineCodeMode, 
+        pub halt_reason: Option<String>, // set when the CPU stopped on something it cannot run
+        machine_routines: HashMap<usize, MachineRoutine>, 
+        font: Font, 
+        font_base: usize, 
+        stack_high_water: usize, // deepest the stack has been since the last reset
+        keys: u16, // bit n is set while key n is held
+        key_wait: KeyWait, 
+        vblank_pending: bool, // a vblank happened that no DXYN has waited for yet
+        display_waiting: bool, // DXYN is stalled until the next vblank
+
         rom_len: usize, 
     }
 
     impl CPU { 
 
         pub fn new() -> Self { 
-            let memory: [u8; 0x1000] = get_fresh_memory_with_font_data(); 
+            CPU::with_limits(DEFAULT_MEMORY_SIZE, DEFAULT_STACK_DEPTH)
+        }
+
+        pub fn with_limits(memory_size: usize, stack_depth: usize) -> Self { 
+            let memory: Vec<u8> = get_fresh_memory_with_font_data(memory_size); 
             CPU { 
                 pixels: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
                 memory, 
                 registers: [0; 16], 
                 reg_i: 0, 
-                stack: [0; MAX_STACK_SIZE], 
+                stack: vec![0; stack_depth], 
                 delay_timer: 0, 
                 sound_timer: 0, 
                 pc: ROM_START_ADDR, 
                 sp: 0,
+                last_key_query: None, 
+                quirks: Quirks::default(), 
+                machine_code: MachineCodeMode::default(), 
+                halt_reason: None, 
+                machine_routines: HashMap::new(), 
+                font: FONT_DATAS, 
+                font_base: DEFAULT_FONT_BASE, 
+                stack_high_water: 0, 
+                keys: 0, 
+                key_wait: KeyWait::Idle, 
+                vblank_pending: false, 
+                display_waiting: false, 
                 rom_len: 0, 
             }
         }
@@ -144,21 +417,124 @@ pub mod cpu {
             for idx in 0..self.registers.len() { 
                 self.registers[idx] = 0x00; 
             }
-            for idx in 0..MAX_STACK_SIZE { 
-                self.stack[idx] = 0x0000; 
-            }
+            self.stack.fill(0x0000); 
+            self.stack_high_water = 0; 
             self.reg_i = 0x0000; 
             self.delay_timer = 0; 
             self.sound_timer = 0; 
             self.pc = ROM_START_ADDR; 
             self.sp = 0;
+            self.last_key_query = None; 
+            self.halt_reason = None; 
+            self.keys = 0; 
+            self.key_wait = KeyWait::Idle; 
+            self.vblank_pending = false; 
+            self.display_waiting = false; 
+        }
+
+        // run routine whenever the program calls machine code at addr, used in dispatch mode
+        pub fn register_routine(&mut self, addr: usize, routine: MachineRoutine) { 
+            self.machine_routines.insert(addr, routine); 
+        }
+
+        pub fn stack_high_water(&self) -> usize { 
+            self.stack_high_water
+        }
+
+        pub fn is_halted(&self) -> bool { 
+            self.halt_reason.is_some()
         }
 
-        pub fn load_rom(&mut self, rom: Vec<u8>) { 
+        // signal the start of a 60 Hz frame, releases a DXYN waiting for the display
+        pub fn vblank(&mut self) { 
+            self.vblank_pending = true; 
+            self.display_waiting = false; 
+        }
+
+        // true while a DXYN is stalled by the display wait quirk, stepping again does nothing until vblank
+        pub fn is_display_waiting(&self) -> bool { 
+            self.display_waiting
+        }
+
+        // keypad events, these update the held keys and drive the wait in FX0A
+        pub fn key_down(&mut self, key: usize) { 
+            self.keys |= 1 << (key & 0xF); 
+            if self.key_wait == KeyWait::WaitingForPress { 
+                self.key_wait = KeyWait::WaitingForRelease(key); 
+            }
+        }
+
+        pub fn key_up(&mut self, key: usize) { 
+            self.keys &= !(1 << (key & 0xF)); 
+            if self.key_wait == KeyWait::WaitingForRelease(key) { 
+                self.key_wait = KeyWait::Released(key); 
+            }
+        }
+
+        // copy rom to x200, it has to fit in the memory above that
+        pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), String> { 
+            self.check_rom(&rom)?; 
             self.rom_len = rom.len(); 
-            for i in 0..rom.len() { 
-                self.memory[ROM_START_ADDR+i] = rom[i]; 
+            self.memory[ROM_START_ADDR..ROM_START_ADDR + rom.len()].copy_from_slice(&rom); 
+            Ok(())
+        }
+
+        // start over with a new rom on fresh memory holding only the font, quirks, limits
+        // and registered routines are kept, a rom that fails the checks leaves the running one untouched
+        pub fn reload_rom(&mut self, rom: Vec<u8>) -> Result<(), String> { 
+            self.check_rom(&rom)?; 
+            let font = self.font; 
+            self.memory.fill(0); 
+            self.load_font(&font, self.font_base)?; 
+            self.reset(); 
+            self.load_rom(rom)
+        }
+
+        fn check_rom(&self, rom: &[u8]) -> Result<(), String> { 
+            let capacity = self.memory.len() - ROM_START_ADDR; 
+            if rom.is_empty() { 
+                return Err("ROM is empty".to_string()); 
+            }
+            if rom.len() > capacity { 
+                return Err(format!(
+                    "ROM is {} bytes but only {} fit between x{:03x} and x{:03x}", 
+                    rom.len(), capacity, ROM_START_ADDR, self.memory.len() - 1
+                )); 
+            }
+            Ok(())
+        }
+
+        pub fn rom_len(&self) -> usize { 
+            self.rom_len
+        }
+
+        pub fn rom_start(&self) -> usize { 
+            ROM_START_ADDR
+        }
+
+        pub fn font_range(&self) -> std::ops::Range<usize> { 
+            self.font_base..self.font_base + FONT_BYTES
+        }
+
+        // font as loaded, the ROM may since have overwritten it in memory
+        pub fn font(&self) -> &Font { 
+            &self.font
+        }
+
+        // replace the font, moving it to base, it has to fit below the ROM
+        pub fn load_font(&mut self, font: &Font, base: usize) -> Result<(), String> { 
+            if base.checked_add(FONT_BYTES).filter(|end| *end <= ROM_START_ADDR).is_none() { 
+                return Err(format!("font at x{:03x} would overlap the ROM at x{:03x}", base, ROM_START_ADDR)); 
+            }
+            let old_range = self.font_range(); 
+            self.memory[old_range].fill(0); 
+            for (i, glyph) in font.iter().enumerate() { 
+                let glyph_addr = base + FONT_HEIGHT * i; 
+                self.memory[glyph_addr..glyph_addr + FONT_HEIGHT].copy_from_slice(glyph); 
             }
+            self.font = *font; 
+            self.font_base = base; 
+            Ok(())
         }
 
         pub fn dump_memory(&self) -> String { 
@@ -181,11 +557,12 @@ pub mod cpu {
             memory_dump 
         }
 
-        fn parse_pressed_keys(target: usize, pressed_keys: Vec<usize>) -> bool { 
-            for key in pressed_keys { 
-                if target == key { return true }
-            }
-            false
+        pub fn keys(&self) -> u16 { 
+            self.keys
+        }
+
+        pub fn is_key_pressed(&self, key: usize) -> bool { 
+            self.keys & (1 << (key & 0xF)) != 0
         }
 
         pub fn update_timers(&mut self) { 
@@ -193,19 +570,26 @@ pub mod cpu {
             if self.sound_timer > 0 { self.sound_timer -= 1; }
         }
 
-        pub fn step(&mut self, pressed_keys: Vec<usize>) { 
+        pub fn step(&mut self) { 
+            if self.is_halted() { 
+                return; 
+            }
             
-            // get next instruction 
+            // get next instruction, a PC run off the end of memory halts instead of reading past it
+            if self.pc + 1 >= self.memory.len() { 
+                self.halt_reason = Some(format!("x{:03x}: program counter ran past the end of memory", self.pc)); 
+                return; 
+            }
             let instruction: usize = ((self.memory[self.pc] as usize) << 8) + self.memory[self.pc+1] as usize; 
             let mut pc_inc: bool = true;
 
             // execute instruction 
             match instruction & 0xF000 { 
                 0x0000 => {
-                    match instruction & 0x00FF { 
+                    match instruction { 
                         0x00E0 => self.opcode_00e0(),
-                        0x00EE => self.opcode_00ee(), 
-                        _ => panic!("invalid opcode found! 0x{:X}", instruction)
+                        0x00EE => pc_inc = self.opcode_00ee(), 
+                        _ => pc_inc = self.opcode_0nnn(instruction), 
                     }
                 }, 
                 0x1000 => { 
@@ -232,25 +616,25 @@ pub mod cpu {
                         0x6 => self.opcode_8xy6(instruction),
                         0x7 => self.opcode_8xy7(instruction),
                         0xE => self.opcode_8xye(instruction),
-                        _ => panic!("invalid opcode found! 0x{:X}", instruction)
+                        _ => pc_inc = self.opcode_unknown(instruction),
                     }
                 },
                 0x9000 => self.opcode_9xy0(instruction),
                 0xA000 => self.opcode_annn(instruction), 
                 0xB000 => self.opcode_bnnn(instruction), 
                 0xC000 => self.opcode_cxnn(instruction), 
-                0xD000 => self.opcode_dxyn(instruction), 
+                0xD000 => pc_inc = self.opcode_dxyn(instruction), 
                 0xE000 => {
                     match instruction & 0x00FF { 
-                        0x009E => self.opcode_ex9e(instruction, pressed_keys),
-                        0x00A1 => self.opcode_exa1(instruction, pressed_keys),
-                        _ => panic!("invalid opcode found! 0x{:X}", instruction)
+                        0x009E => self.opcode_ex9e(instruction),
+                        0x00A1 => self.opcode_exa1(instruction),
+                        _ => pc_inc = self.opcode_unknown(instruction),
                     }
                 },
                 0xF000 => {
                     match instruction & 0x00FF { 
                         0x0007 => self.opcode_fx07(instruction),
-                        0x000A => pc_inc = self.opcode_fx0a(instruction, pressed_keys),
+                        0x000A => pc_inc = self.opcode_fx0a(instruction),
                         0x0015 => self.opcode_fx15(instruction),
                         0x0018 => self.opcode_fx18(instruction),
                         0x001E => self.opcode_fx1e(instruction),
@@ -258,10 +642,14 @@ pub mod cpu {
                         0x0033 => self.opcode_fx33(instruction),
                         0x0055 => self.opcode_fx55(instruction),
                         0x0065 => self.opcode_fx65(instruction),
-                        _ => panic!("invalid opcode found! 0x{:X}", instruction)
+                        0x0000 if instruction == LONG_LOAD_OPCODE && self.has_long_addressing() => { 
+                            pc_inc = false; 
+                            self.opcode_f000_nnnn()
+                        },
+                        _ => pc_inc = self.opcode_unknown(instruction),
                     }
                 },
-                _ => panic!("invalid opcode found! 0x{:X}", instruction) 
+                _ => pc_inc = self.opcode_unknown(instruction), 
             }
 
             // increment pc 
@@ -280,9 +668,54 @@ pub mod cpu {
         }
 
         // return from subroutine
-        fn opcode_00ee(&mut self) {
+        fn opcode_00ee(&mut self) -> bool {
+            if self.sp == 0 { 
+                self.halt_reason = Some(format!("x{:03x}: return with an empty call stack", self.pc)); 
+                return false; 
+            }
             self.sp -= 1; 
             self.pc = self.stack[self.sp];
+            true
+        }
+
+        // memory past 4 KiB can only be reached through I, with the XO-CHIP long load
+        fn has_long_addressing(&self) -> bool { 
+            self.memory.len() > DEFAULT_MEMORY_SIZE
+        }
+
+        // skips step over the whole of a 4 byte long load
+        fn skip_next_instruction(&mut self) { 
+            let next = self.pc + 2; 
+            let is_long_load = self.has_long_addressing() 
+                && self.memory.get(next) == Some(&0xF0) 
+                && self.memory.get(next + 1) == Some(&0x00); 
+            self.pc += if is_long_load { 4 } else { 2 }; 
+        }
+
+        // an instruction this interpreter does not implement (e.g. SCHIP or XO-CHIP only)
+        // halts with the PC left on it instead of crashing
+        fn opcode_unknown(&mut self, instruction: usize) -> bool { 
+            self.halt_reason = Some(format!("x{:03x}: unknown instruction {:04x}", self.pc, instruction)); 
+            false
+        }
+
+        // call machine code routine at nnn
+        // returns false when the CPU halts so the PC stays on the call
+        fn opcode_0nnn(&mut self, instruction: usize) -> bool { 
+            let addr = instruction & 0x0FFF; 
+            let routine = self.machine_routines.get(&addr).copied(); 
+            match (self.machine_code, routine) { 
+                (MachineCodeMode::Ignore, _) => {}, 
+                (MachineCodeMode::Dispatch, Some(routine)) => routine(self), 
+                _ => { 
+                    self.halt_reason = Some(format!(
+                        "x{:03x}: call to machine code routine at x{:03x} is not supported", 
+                        self.pc, addr
+                    )); 
+                    return false; 
+                }
+            }
+            true
         }
 
         // jump to nnn
@@ -292,30 +725,32 @@ pub mod cpu {
 
         // call subroutine 
         fn opcode_2nnn(&mut self, instruction: usize) { 
-            if self.sp >= MAX_STACK_SIZE { 
-                panic!("Stack is too full to push new return addresses"); 
+            if self.sp >= self.stack.len() { 
+                self.halt_reason = Some(format!("x{:03x}: call stack overflow, calls nest deeper than {}", self.pc, self.stack.len())); 
+                return; 
             }
             self.stack[self.sp] = self.pc; 
             self.sp += 1; 
+            self.stack_high_water = self.stack_high_water.max(self.sp); 
             self.pc = instruction & 0xFFF; 
         }
 
         fn opcode_3xnn(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let nn: usize = instruction & 0x00FF;
-            if self.registers[x] == nn as u8 { self.pc += 2; }
+            if self.registers[x] == nn as u8 { self.skip_next_instruction(); }
         }
 
         fn opcode_4xnn(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let nn: usize = instruction & 0x00FF;
-            if self.registers[x] != nn as u8 { self.pc += 2; }
+            if self.registers[x] != nn as u8 { self.skip_next_instruction(); }
         }
 
         fn opcode_5xy0(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let y: usize = (instruction & 0x00F0) >> 4;
-            if self.registers[x] == self.registers[y] { self.pc += 2; }
+            if self.registers[x] == self.registers[y] { self.skip_next_instruction(); }
         }
 
         // Vx = NN
@@ -359,67 +794,62 @@ pub mod cpu {
             self.registers[x] ^= self.registers[y];
         }
 
-        // Vx += Vy
+        // Vx += Vy, VF = carry
+        // VF is written after the result so the flag wins when X is F
         fn opcode_8xy4(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let y: usize = (instruction & 0x00F0) >> 4;
-
-            if self.registers[x] as usize + self.registers[y] as usize > u8::MAX as usize{ 
-                self.registers[0xF] = 1; 
-            } else { 
-                self.registers[0xF] = 0; 
-            }
-
-            self.registers[x] = ((self.registers[x] as usize + self.registers[y] as usize) & 0xFF) as u8 ;
+            let (result, carry) = self.registers[x].overflowing_add(self.registers[y]); 
+            self.registers[x] = result; 
+            self.registers[0xF] = carry as u8; 
         }
 
-        // Vx -= Vy
+        // Vx -= Vy, VF = not borrow
         fn opcode_8xy5(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let y: usize = (instruction & 0x00F0) >> 4;
-
-            if (self.registers[x] as isize - self.registers[y] as isize) < 0 { 
-                self.registers[0xF] = 1; 
-            } else { 
-                self.registers[0xF] = 0; 
-            }
-
-            self.registers[x] = ((self.registers[x] as isize - self.registers[y] as isize) & 0xFF) as u8 ;
+            let (result, borrow) = self.registers[x].overflowing_sub(self.registers[y]); 
+            self.registers[x] = result; 
+            self.registers[0xF] = !borrow as u8; 
         }
 
-        // Vx >>= 1
+        // Vx >>= 1, VF = bit shifted out
         fn opcode_8xy6(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
-            self.registers[0xF] = self.registers[x] & 0x1; 
-            self.registers[x] >>= 1;  
+            let source: u8 = self.shift_source(instruction); 
+            self.registers[x] = source >> 1;  
+            self.registers[0xF] = source & 0x1; 
         }
 
-        // Vx = Vy - Vx
+        // Vx = Vy - Vx, VF = not borrow
         fn opcode_8xy7(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let y: usize = (instruction & 0x00F0) >> 4;
-
-            if (self.registers[y] as isize - self.registers[x] as isize) < 0 { 
-                self.registers[0xF] = 1; 
-            } else { 
-                self.registers[0xF] = 0; 
-            }
-
-            self.registers[x] = ((self.registers[y] as isize - self.registers[x] as isize) & 0xFF) as u8 ;
+            let (result, borrow) = self.registers[y].overflowing_sub(self.registers[x]); 
+            self.registers[x] = result; 
+            self.registers[0xF] = !borrow as u8; 
         }
 
-        // Vx <<= 1
+        // Vx <<= 1, VF = bit shifted out
         fn opcode_8xye(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
-            self.registers[0xF] = if self.registers[x] & 0x80 == 0 { 0 } else { 1 }; 
-            self.registers[x] <<= 1;  
+            let source: u8 = self.shift_source(instruction); 
+            self.registers[x] = source << 1;  
+            self.registers[0xF] = source >> 7; 
+        }
+
+        // the VIP shifts Vy into Vx, CHIP-48 and later shift Vx itself
+        fn shift_source(&self, instruction: usize) -> u8 { 
+            let x: usize = (instruction & 0x0F00) >> 8; 
+            let y: usize = (instruction & 0x00F0) >> 4;
+            if self.quirks.shift_in_place { self.registers[x] } else { self.registers[y] }
         }
 
         // skip if Vx != Vy
         fn opcode_9xy0(&mut self, instruction: usize) { 
             let x: usize = (instruction & 0x0F00) >> 8; 
             let y: usize = (instruction & 0x00F0) >> 4;
-            if self.registers[x] != self.registers[y] { self.pc += 2; }
+            if self.registers[x] != self.registers[y] { self.skip_next_instruction(); }
         }
 
         // I = NNN
@@ -440,58 +870,111 @@ pub mod cpu {
         }
 
         // draw(Vx, Vy), N rows of 8 pixels
-        fn opcode_dxyn(&mut self, instruction: usize) { 
+        // returns false while waiting for vblank with the display wait quirk
+        fn opcode_dxyn(&mut self, instruction: usize) -> bool { 
+            if self.quirks.display_wait { 
+                if !self.vblank_pending { 
+                    self.display_waiting = true; 
+                    return false; 
+                }
+                self.vblank_pending = false; 
+            }
+
             let x = (instruction & 0x0F00) >> 8; 
             let y = (instruction & 0x00F0) >> 4; 
             let n = instruction & 0xF;
 
-            let start_x = self.registers[x] as usize; 
-            let start_y = self.registers[y] as usize; 
-            let sprite_start = start_y * SCREEN_WIDTH + start_x; 
+            // the starting position always wraps, the rest of the sprite is clipped
+            // at the screen edges unless the wrap quirk is on
+            let start_x = self.registers[x] as usize % SCREEN_WIDTH; 
+            let start_y = self.registers[y] as usize % SCREEN_HEIGHT; 
 
-            self.registers[0xF] = 0; 
+            let mut collided_rows: u8 = 0; 
             for row_idx in 0..n { 
-                let mut row: u8 = self.memory[self.reg_i + row_idx]; 
-                for col_idx in 0..8 { 
-                    let pixels_idx = sprite_start + row_idx * SCREEN_WIDTH + col_idx; 
-                    if pixels_idx >= self.pixels.len() { continue; }
+                let mut pixel_y = start_y + row_idx; 
+                if pixel_y >= SCREEN_HEIGHT { 
+                    if !self.quirks.sprite_wrap { break; }
+                    pixel_y %= SCREEN_HEIGHT; 
+                }
 
-                    let sprite_pixel = (row >> 7) & 0x1; 
-                    let existing_pixel = self.pixels[pixels_idx] as u8; 
-                    if existing_pixel == 1 && sprite_pixel == 1 {
-                        self.registers[0xF] = 1;
+                // sprite data past the end of memory wraps back to the start
+                let row: u8 = self.memory[(self.reg_i + row_idx) % self.memory.len()]; 
+                let mut row_collided = false; 
+                for col_idx in 0..8 { 
+                    let mut pixel_x = start_x + col_idx; 
+                    if pixel_x >= SCREEN_WIDTH { 
+                        if !self.quirks.sprite_wrap { break; }
+                        pixel_x %= SCREEN_WIDTH; 
                     }
-                    self.pixels[pixels_idx] = existing_pixel ^ sprite_pixel == 1; 
-                    row <<= 1; 
+                    if (row >> (7 - col_idx)) & 0x1 == 0 { continue; }
+
+                    let pixels_idx = pixel_y * SCREEN_WIDTH + pixel_x; 
+                    row_collided |= self.pixels[pixels_idx]; 
+                    self.pixels[pixels_idx] = !self.pixels[pixels_idx]; 
                 }
+                collided_rows += row_collided as u8; 
             }
+
+            self.registers[0xF] = match self.quirks.collision_rows { 
+                true => collided_rows, 
+                false => (collided_rows > 0) as u8, 
+            }; 
+            true
         }
 
         // skip if key() == Vx
-        fn opcode_ex9e(&mut self, instruction: usize, pressed_keys: Vec<usize>) {
+        fn opcode_ex9e(&mut self, instruction: usize) {
             let x: usize = (instruction & 0x0F00) >> 8; 
-            if CPU::parse_pressed_keys(x, pressed_keys) { 
-                self.pc += 2; 
+            let key: usize = (self.registers[x] & 0xF) as usize; 
+            self.last_key_query = Some(KeyQuery::Key(key)); 
+            if self.is_key_pressed(key) { 
+                self.skip_next_instruction(); 
             }
         }
 
         // skip if key() != Vx
-        fn opcode_exa1(&mut self, instruction: usize, pressed_keys: Vec<usize>) {
+        fn opcode_exa1(&mut self, instruction: usize) {
             let x: usize = (instruction & 0x0F00) >> 8; 
-            if !CPU::parse_pressed_keys(x, pressed_keys) { 
-                self.pc += 2; 
+            let key: usize = (self.registers[x] & 0xF) as usize; 
+            self.last_key_query = Some(KeyQuery::Key(key)); 
+            if !self.is_key_pressed(key) { 
+                self.skip_next_instruction(); 
             }
         }
 
         // Vx = key()
+        // blocks until a key is pressed and released again like the COSMAC VIP, a key that is
+        // already held when the wait starts counts as pressed, so holding a key selects it once
         // returns true if key press was found and recorded in Vx
-        fn opcode_fx0a(&mut self, instruction: usize, pressed_keys: Vec<usize>) -> bool {
-            if pressed_keys.len() == 0 { 
-                return false;
-            }
+        fn opcode_fx0a(&mut self, instruction: usize) -> bool {
+            self.last_key_query = Some(KeyQuery::AnyKey); 
             let x: usize = (instruction & 0x0F00) >> 8; 
-            self.registers[x] = pressed_keys[0] as u8;
-            true 
+
+            if self.quirks.fx0a_immediate { 
+                return match (0..16).find(|key| self.is_key_pressed(*key)) { 
+                    Some(key) => { 
+                        self.registers[x] = key as u8; 
+                        true
+                    }, 
+                    None => false, 
+                }; 
+            }
+
+            match self.key_wait { 
+                KeyWait::Idle => { 
+                    self.key_wait = match (0..16).find(|key| self.is_key_pressed(*key)) { 
+                        Some(key) => KeyWait::WaitingForRelease(key), 
+                        None => KeyWait::WaitingForPress, 
+                    }; 
+                    false
+                }, 
+                KeyWait::WaitingForPress | KeyWait::WaitingForRelease(_) => false, 
+                KeyWait::Released(key) => { 
+                    self.key_wait = KeyWait::Idle; 
+                    self.registers[x] = key as u8; 
+                    true
+                }, 
+            }
         }
 
         // Vx = delay_timer
@@ -522,7 +1005,7 @@ pub mod cpu {
         fn opcode_fx29(&mut self, instruction: usize) {
             let x: usize = (instruction & 0x0F00) >> 8; 
             let vx: usize = (self.registers[x] & 0xF) as usize; 
-            self.reg_i = FONT_SPRITE_START_ADDRS[vx]; 
+            self.reg_i = self.font_base + FONT_HEIGHT * vx; 
         }
 
         // BCD of Vx stored in I -> if Vx = 123 then I = 1, I+1 = 2, I+2 = 3
@@ -530,27 +1013,37 @@ pub mod cpu {
             let x: usize = (instruction & 0x0F00) >> 8; 
             let vx: u8 = self.registers[x]; 
             
-            self.memory[self.reg_i+2] = vx % 10;
-            self.memory[self.reg_i+1] = (vx / 10) % 10; 
-            self.memory[self.reg_i] = (vx / 100) % 10; 
+            let len = self.memory.len(); 
+            self.memory[(self.reg_i+2) % len] = vx % 10;
+            self.memory[(self.reg_i+1) % len] = (vx / 10) % 10; 
+            self.memory[self.reg_i % len] = (vx / 100) % 10; 
         }
 
         // LD [I], Vx -> store V0-Vx in memory starting at I
         fn opcode_fx55(&mut self, instruction: usize) {
             let x: usize = (instruction & 0x0F00) >> 8; 
+            let len = self.memory.len(); 
             for i in 0..=x { 
-                self.memory[self.reg_i+i] = self.registers[i]; 
+                self.memory[(self.reg_i+i) % len] = self.registers[i]; 
             }
         }
 
         // LD Vx, [I] -> load V0-Vx with memory starting at I
         fn opcode_fx65(&mut self, instruction: usize) {
             let x: usize = (instruction & 0x0F00) >> 8; 
+            let len = self.memory.len(); 
             for i in 0..=x { 
-                self.registers[i] = self.memory[self.reg_i+i]; 
+                self.registers[i] = self.memory[(self.reg_i+i) % len]; 
             }
         }
 
+        // I = NNNN from the word after the instruction, then skip both words
+        fn opcode_f000_nnnn(&mut self) { 
+            let len = self.memory.len(); 
+            self.reg_i = ((self.memory[(self.pc+2) % len] as usize) << 8) + self.memory[(self.pc+3) % len] as usize; 
+            self.pc += 4; 
+        }
+
     }
 
 }
@@ -558,7 +1051,31 @@ pub mod cpu {
 
 #[cfg(test)]
 mod tests { 
-    use super::cpu::CPU; 
+    use super::cpu::{CPU, DEFAULT_STACK_DEPTH, FONT_DATAS, FONT_HEIGHT, FontSet, KeyQuery, MachineCodeMode, Platform, Quirks, SCREEN_WIDTH, builtin_routine, parse_font, parse_routine}; 
+
+    const KEYPAD_TEST_ROM: &[u8] = include_bytes!("../assets/Keypad Test [Hap, 2006].ch8"); 
+    const KEYPAD_TEST_WAIT_ADDR: usize = 0x264; // FX0A in the keypad test main loop
+    const KEYPAD_TEST_DELAY_ADDR: usize = 0x246; // delay loop run while a key is highlighted
+
+    fn run_until(cpu: &mut CPU, addr: usize, max_steps: usize) -> bool { 
+        for _ in 0..max_steps { 
+            if cpu.pc == addr { return true; }
+            cpu.step(); 
+        }
+        cpu.pc == addr
+    }
+
+    // the keypad test shifts its registers in place like CHIP-48
+    fn keypad_test_cpu() -> CPU { 
+        let mut cpu = CPU::new(); 
+        cpu.quirks.shift_in_place = true; 
+        cpu.load_rom(KEYPAD_TEST_ROM.to_vec()).unwrap(); 
+        cpu
+    }
+
+    fn pixel(cpu: &CPU, x: usize, y: usize) -> bool { 
+        cpu.pixels[y * SCREEN_WIDTH + x]
+    }
 
     #[test] 
     fn should_clear_screen_when_opcode_00e0() { 
@@ -568,7 +1085,7 @@ mod tests {
         cpu.pixels[0] = true; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(!cpu.pixels[0]);
     }
@@ -582,7 +1099,7 @@ mod tests {
         cpu.sp = 1; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.sp == 0); 
         assert!(cpu.pc == 0xFFC + 2); 
@@ -595,7 +1112,7 @@ mod tests {
         cpu.memory[0] = 0x11; 
         cpu.memory[1] = 0x20;
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.pc == 0x120); 
     }
@@ -607,7 +1124,7 @@ mod tests {
         cpu.memory[1] = 0xFC;
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.stack[0] == 0x0000);
         assert!(cpu.sp == 1); 
@@ -622,7 +1139,7 @@ mod tests {
         cpu.registers[1] = 0x23; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.pc == 0x0004); 
     }
@@ -635,7 +1152,7 @@ mod tests {
         cpu.registers[1] = 0x00;
         cpu.pc = 0;  
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.pc == 0x0004); 
     }
@@ -649,7 +1166,7 @@ mod tests {
         cpu.registers[2] = 0x8; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.pc == 0x0004); 
     }
@@ -661,7 +1178,7 @@ mod tests {
         cpu.memory[1] = 0x20;
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.registers[1] == 0x20); 
     }
@@ -674,7 +1191,7 @@ mod tests {
         cpu.registers[1] = 0xF; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
 
         assert!(cpu.registers[1] == 0x2F); 
     }
@@ -688,9 +1205,540 @@ mod tests {
         cpu.reg_i = 2; 
         cpu.pc = 0; 
 
-        cpu.step(Vec::new()); 
+        cpu.step(); 
         assert!(cpu.memory[cpu.reg_i] == 1); 
         assert!(cpu.memory[cpu.reg_i+1] == 3);
         assert!(cpu.memory[cpu.reg_i+2] == 7); 
     }
-}
\ No newline at end of file
+
+    #[test] 
+    fn should_record_key_queried_by_skip_and_wait_opcodes() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xE5, 0xA1, 0xF0, 0x0A]).unwrap(); 
+        cpu.registers[5] = 0xB; 
+        assert!(cpu.last_key_query.is_none()); 
+
+        cpu.step(); 
+        assert!(cpu.last_key_query == Some(KeyQuery::Key(0xB))); 
+
+        cpu.pc = 0x202; 
+        cpu.step(); 
+        assert!(cpu.last_key_query == Some(KeyQuery::AnyKey)); 
+    }
+
+    #[test] 
+    fn should_wait_for_press_and_release_when_opcode_fx0a() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xF3, 0x0A]).unwrap(); 
+
+        cpu.step(); 
+        cpu.key_down(0x5); 
+        cpu.step(); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x200); 
+
+        cpu.key_up(0x5); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202); 
+        assert!(cpu.registers[3] == 0x5); 
+    }
+
+    #[test] 
+    fn should_take_key_held_when_fx0a_starts_once_released() { 
+        // the VIP reads the key lines directly, so a held key counts as pressed right away
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xF3, 0x0A, 0xF4, 0x0A]).unwrap(); 
+        cpu.key_down(0x7); 
+
+        cpu.step(); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x200); 
+
+        cpu.key_up(0x7); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202 && cpu.registers[3] == 0x7); 
+
+        // and once taken it is not taken again by the next wait
+        cpu.step(); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202); 
+    }
+
+    #[test] 
+    fn should_take_lowest_held_key_when_fx0a_immediate_quirk() { 
+        let mut cpu = CPU::new(); 
+        cpu.quirks = Quirks::parse("fx0a-immediate").unwrap(); 
+        cpu.load_rom(vec![0xF3, 0x0A]).unwrap(); 
+
+        cpu.key_down(0xC); 
+        cpu.key_down(0x4); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202); 
+        assert!(cpu.registers[3] == 0x4); 
+        assert!(Quirks::parse("fx0a-later").is_err()); 
+    }
+
+    #[test] 
+    fn should_skip_on_key_in_vx_when_opcode_ex9e() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xE1, 0x9E, 0x00, 0x00, 0x00, 0x00]).unwrap(); 
+        cpu.registers[1] = 0xA; 
+
+        // the register index is not the key being tested
+        cpu.key_down(0x1); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202); 
+
+        cpu.pc = 0x200; 
+        cpu.key_down(0xA); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x204); 
+        assert!(cpu.keys() == 0b0000_0100_0000_0010); 
+    }
+
+    #[test] 
+    fn should_skip_unless_key_in_vx_when_opcode_exa1() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xE1, 0xA1, 0x00, 0x00, 0x00, 0x00]).unwrap(); 
+        cpu.registers[1] = 0xA; 
+
+        cpu.key_down(0xA); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x202); 
+
+        cpu.pc = 0x200; 
+        cpu.key_up(0xA); 
+        cpu.step(); 
+        assert!(cpu.pc == 0x204); 
+    }
+
+    #[test] 
+    fn should_draw_all_keys_and_wait_when_running_keypad_test_rom() { 
+        let mut cpu = keypad_test_cpu(); 
+
+        assert!(run_until(&mut cpu, KEYPAD_TEST_WAIT_ADDR, 1_000)); 
+        // top rows of the "1", "E" and "F" digits in the keypad grid
+        assert!(pixel(&cpu, 0x03, 0x01)); 
+        assert!(pixel(&cpu, 0x16, 0x11)); 
+        assert!(pixel(&cpu, 0x16, 0x19)); 
+
+        // nothing happens while no key is pressed
+        for _ in 0..100 { cpu.step(); }
+        assert!(cpu.pc == KEYPAD_TEST_WAIT_ADDR); 
+    }
+
+    #[test] 
+    fn should_highlight_key_after_release_when_running_keypad_test_rom() { 
+        let mut cpu = keypad_test_cpu(); 
+        assert!(run_until(&mut cpu, KEYPAD_TEST_WAIT_ADDR, 1_000)); 
+
+        // holding the key keeps the rom waiting, releasing it selects the key
+        cpu.key_down(0xA); 
+        for _ in 0..10 { cpu.step(); }
+        assert!(cpu.pc == KEYPAD_TEST_WAIT_ADDR); 
+        cpu.key_up(0xA); 
+
+        // a box is drawn around the "A" digit in the bottom left corner
+        assert!(run_until(&mut cpu, KEYPAD_TEST_DELAY_ADDR, 100)); 
+        assert!(cpu.registers[2] == 0xA); 
+        assert!(pixel(&cpu, 0x00, 0x18)); 
+
+        // and removed again once the delay timer runs out
+        for _ in 0..32 { 
+            for _ in 0..10 { cpu.step(); }
+            cpu.update_timers(); 
+        }
+        assert!(run_until(&mut cpu, KEYPAD_TEST_WAIT_ADDR, 100)); 
+        assert!(!pixel(&cpu, 0x00, 0x18)); 
+    }
+
+    fn draw_sprite(cpu: &mut CPU, x: u8, y: u8, sprite: &[u8]) { 
+        cpu.memory[0x300..0x300 + sprite.len()].copy_from_slice(sprite); 
+        cpu.reg_i = 0x300; 
+        cpu.registers[0] = x; 
+        cpu.registers[1] = y; 
+        cpu.memory[0x200] = 0xD0; 
+        cpu.memory[0x201] = 0x10 | sprite.len() as u8; 
+        cpu.pc = 0x200; 
+        cpu.step(); 
+    }
+
+    #[test] 
+    fn should_clip_sprite_at_right_and_bottom_edges_when_opcode_dxyn() { 
+        let mut cpu = CPU::new(); 
+
+        draw_sprite(&mut cpu, 60, 30, &[0xFF, 0xFF, 0xFF]); 
+
+        assert!(pixel(&cpu, 63, 30) && pixel(&cpu, 63, 31)); 
+        // nothing bleeds onto the next row or wraps to the top
+        assert!(!pixel(&cpu, 0, 31) && !pixel(&cpu, 0, 0) && !pixel(&cpu, 60, 0)); 
+        assert!(cpu.pixels.iter().filter(|lit| **lit).count() == 8); 
+    }
+
+    #[test] 
+    fn should_wrap_sprite_around_edges_when_sprite_wrap_quirk() { 
+        let mut cpu = CPU::new(); 
+        cpu.quirks = Quirks::parse("sprite-wrap").unwrap(); 
+
+        draw_sprite(&mut cpu, 60, 31, &[0xFF, 0xFF]); 
+
+        assert!(pixel(&cpu, 63, 31) && pixel(&cpu, 0, 31) && pixel(&cpu, 3, 31)); 
+        assert!(pixel(&cpu, 60, 0) && pixel(&cpu, 3, 0)); 
+        assert!(cpu.pixels.iter().filter(|lit| **lit).count() == 16); 
+    }
+
+    #[test] 
+    fn should_wrap_starting_coordinates_when_opcode_dxyn() { 
+        let mut cpu = CPU::new(); 
+
+        draw_sprite(&mut cpu, 64 + 5, 32 + 2, &[0x80]); 
+
+        assert!(pixel(&cpu, 5, 2)); 
+        assert!(cpu.pixels.iter().filter(|lit| **lit).count() == 1); 
+    }
+
+    #[test] 
+    fn should_set_vf_on_collision_and_erase_when_opcode_dxyn() { 
+        let mut cpu = CPU::new(); 
+
+        draw_sprite(&mut cpu, 10, 10, &[0xC0, 0xC0]); 
+        assert!(cpu.registers[0xF] == 0); 
+
+        draw_sprite(&mut cpu, 11, 11, &[0x80]); 
+        assert!(cpu.registers[0xF] == 1); 
+        assert!(!pixel(&cpu, 11, 11) && pixel(&cpu, 10, 11)); 
+    }
+
+    #[test] 
+    fn should_count_collided_rows_when_collision_rows_quirk() { 
+        let mut cpu = CPU::new(); 
+        cpu.quirks = Quirks::parse("collision-rows").unwrap(); 
+
+        draw_sprite(&mut cpu, 0, 0, &[0xFF, 0x00, 0xFF]); 
+        draw_sprite(&mut cpu, 0, 0, &[0x80, 0x80, 0x81]); 
+
+        assert!(cpu.registers[0xF] == 2); 
+    }
+
+    #[test] 
+    fn should_wrap_sprite_reads_past_end_of_memory_when_opcode_dxyn() { 
+        let mut cpu = CPU::new(); 
+        cpu.memory[0xFFF] = 0x80; 
+        cpu.memory[0x000] = 0x40; 
+        cpu.reg_i = 0xFFF; 
+        cpu.load_rom(vec![0xD0, 0x12]).unwrap(); 
+
+        cpu.step(); 
+
+        assert!(pixel(&cpu, 0, 0) && pixel(&cpu, 1, 1)); 
+    }
+
+    #[test] 
+    fn should_halt_on_unknown_instruction() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0xF1, 0x30, 0xE1, 0x00]).unwrap(); 
+
+        cpu.step(); 
+        assert!(cpu.is_halted() && cpu.pc == 0x200); 
+        assert!(cpu.halt_reason.as_deref().unwrap().contains("f130")); 
+
+        cpu.reset(); 
+        cpu.pc = 0x202; 
+        cpu.step(); 
+        assert!(cpu.is_halted() && cpu.pc == 0x202); 
+    }
+
+    #[test] 
+    fn should_halt_with_reason_on_machine_code_call_by_default() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0x02, 0xF0, 0x60, 0x01]).unwrap(); 
+
+        cpu.step(); 
+        cpu.step(); 
+
+        assert!(cpu.is_halted()); 
+        assert!(cpu.pc == 0x200); 
+        assert!(cpu.halt_reason.as_deref().unwrap().contains("x2f0")); 
+        cpu.reset(); 
+        assert!(!cpu.is_halted()); 
+    }
+
+    #[test] 
+    fn should_skip_machine_code_call_when_ignored() { 
+        let mut cpu = CPU::new(); 
+        cpu.machine_code = MachineCodeMode::Ignore; 
+        cpu.load_rom(vec![0x02, 0xF0, 0x60, 0x01]).unwrap(); 
+
+        cpu.step(); 
+        cpu.step(); 
+
+        assert!(!cpu.is_halted()); 
+        assert!(cpu.registers[0] == 1); 
+    }
+
+    #[test] 
+    fn should_dispatch_machine_code_call_to_registered_routine() { 
+        let mut cpu = CPU::new(); 
+        cpu.machine_code = MachineCodeMode::Dispatch; 
+        let (addr, routine) = parse_routine("0x2f0=cls").unwrap(); 
+        cpu.register_routine(addr, routine); 
+        cpu.register_routine(0x300, builtin_routine("nop").unwrap()); 
+        cpu.pixels[0] = true; 
+        cpu.load_rom(vec![0x02, 0xF0, 0x03, 0x00, 0x03, 0x02]).unwrap(); 
+
+        cpu.step(); 
+        cpu.step(); 
+        assert!(!cpu.pixels[0] && cpu.pc == 0x204 && !cpu.is_halted()); 
+
+        cpu.step(); 
+        assert!(cpu.is_halted()); 
+        assert!(parse_routine("0x2f0=missing").is_err() && parse_routine("cls").is_err()); 
+    }
+
+    #[test] 
+    fn should_load_all_sixteen_glyphs_when_cpu_created() { 
+        let cpu = CPU::new(); 
+        let font = &cpu.memory[cpu.font_range()]; 
+
+        assert!(font.len() == 16 * FONT_HEIGHT); 
+        assert!(font[0xF * FONT_HEIGHT..] == FONT_DATAS[0xF]); 
+    }
+
+    #[test] 
+    fn should_point_i_at_relocated_glyph_when_opcode_fx29() { 
+        let mut cpu = CPU::new(); 
+        let font = FontSet::Vip.glyphs(); 
+        cpu.load_font(font, 0x050).unwrap(); 
+        cpu.registers[3] = 0xF; 
+        cpu.load_rom(vec![0xF3, 0x29]).unwrap(); 
+
+        cpu.step(); 
+
+        assert!(cpu.reg_i == 0x050 + 0xF * FONT_HEIGHT); 
+        assert!(cpu.memory[cpu.reg_i..cpu.reg_i + FONT_HEIGHT] == font[0xF]); 
+        assert!(cpu.memory[0x000..0x050].iter().all(|byte| *byte == 0)); 
+        assert!(cpu.load_font(font, 0x1C0).is_err()); 
+    }
+
+    #[test] 
+    fn should_reject_font_base_that_overflows_address_space() { 
+        let mut cpu = CPU::new(); 
+
+        assert!(cpu.load_font(FontSet::Vip.glyphs(), usize::MAX).is_err()); 
+        assert!(cpu.font_range() == (0x000..FONT_HEIGHT * 16)); 
+    }
+
+    #[test] 
+    fn should_parse_custom_font_of_exactly_eighty_bytes() { 
+        let bytes: Vec<u8> = (0..80).collect(); 
+        let font = parse_font(&bytes).unwrap(); 
+
+        assert!(font[1] == [5, 6, 7, 8, 9]); 
+        assert!(parse_font(&bytes[..79]).is_err()); 
+    }
+
+    #[test] 
+    fn should_halt_on_call_stack_overflow_and_track_high_water_mark() { 
+        // a subroutine that calls itself forever
+        let mut cpu = CPU::with_limits(0x1000, 4); 
+        cpu.load_rom(vec![0x22, 0x00]).unwrap(); 
+
+        for _ in 0..10 { cpu.step(); }
+
+        assert!(cpu.is_halted()); 
+        assert!(cpu.sp == 4 && cpu.stack_high_water() == 4); 
+        assert!(cpu.halt_reason.as_deref().unwrap().contains("overflow")); 
+        cpu.reset(); 
+        assert!(cpu.stack_high_water() == 0); 
+    }
+
+    #[test] 
+    fn should_halt_on_return_with_empty_stack_when_opcode_00ee() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0x00, 0xEE]).unwrap(); 
+
+        cpu.step(); 
+
+        assert!(cpu.is_halted() && cpu.pc == 0x200); 
+    }
+
+    #[test] 
+    fn should_halt_when_pc_runs_past_end_of_memory() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0x1F, 0xFF]).unwrap(); 
+
+        cpu.step(); 
+        cpu.step(); 
+
+        assert!(cpu.is_halted() && cpu.pc == 0xFFF); 
+    }
+
+    #[test] 
+    fn should_load_long_address_into_i_and_skip_over_it_with_64k_memory() { 
+        let mut cpu = CPU::with_limits(0x10000, DEFAULT_STACK_DEPTH); 
+        cpu.load_rom(vec![0xF0, 0x00, 0xE0, 0x00, 0x60, 0x2A, 0xF0, 0x55, 0x30, 0x2A, 0xF0, 0x00, 0xFF, 0xFF]).unwrap(); 
+
+        cpu.step(); 
+        assert!(cpu.reg_i == 0xE000 && cpu.pc == 0x204); 
+
+        cpu.step(); 
+        cpu.step(); 
+        assert!(cpu.memory[0xE000] == 0x2A); 
+
+        cpu.step(); 
+        assert!(cpu.pc == 0x20E); 
+    }
+
+    #[test] 
+    fn should_size_stack_and_memory_by_platform() { 
+        let vip = Platform::from_name("vip").unwrap(); 
+        let schip = Platform::from_name("schip").unwrap(); 
+        let xochip = Platform::from_name("xochip").unwrap(); 
+        let cpu = CPU::with_limits(xochip.memory_size(), xochip.stack_depth()); 
+
+        assert!(vip.stack_depth() == 12 && schip.stack_depth() == 16 && xochip.stack_depth() > 16); 
+        assert!(cpu.memory.len() == 0x10000 && cpu.stack.len() == xochip.stack_depth()); 
+        assert!(cpu.memory[cpu.font_range()] == FONT_DATAS.concat()[..]); 
+    }
+
+    #[test] 
+    fn should_start_over_on_fresh_memory_when_rom_reloaded() { 
+        let mut cpu = CPU::new(); 
+        cpu.load_rom(vec![0x60, 0x01, 0x12, 0x02, 0xAB]).unwrap(); 
+        cpu.step(); 
+        cpu.memory[0x000] = 0xFF; 
+
+        cpu.reload_rom(vec![0x61, 0x02]).unwrap(); 
+
+        assert!(cpu.pc == 0x200 && cpu.registers[0] == 0); 
+        assert!(cpu.memory[0x000] == FONT_DATAS[0][0]); 
+        assert!(cpu.memory[0x204] == 0 && cpu.rom_len() == 2); 
+        assert!(cpu.reload_rom(vec![0; 0x1000]).is_err() && cpu.rom_len() == 2); 
+
+        cpu.step(); 
+        assert!(cpu.reload_rom(Vec::new()).is_err()); 
+        assert!(cpu.pc == 0x202 && cpu.registers[1] == 0x02); 
+    }
+
+    // table driven checks of the 8XYN arithmetic and logic instructions against
+    // reference results, including the cases where X or Y is the flag register
+
+    // value VF holds before each case so untouched flags can be told apart
+    const FLAG_SENTINEL: u8 = 0xAA;
+
+    struct ArithmeticCase {
+        opcode: u16,
+        x_value: u8, // Vx before the instruction
+        y_value: u8, // Vy before the instruction, written after Vx so it wins when X == Y
+        result: u8,  // expected Vx, ignored when X is F
+        flag: u8,    // expected VF
+    }
+
+    const ARITHMETIC_CASES: [ArithmeticCase; 34] = [
+        // logic and loads leave VF alone
+        ArithmeticCase { opcode: 0x8120, x_value: 0x12, y_value: 0x34, result: 0x34, flag: FLAG_SENTINEL },
+        ArithmeticCase { opcode: 0x8121, x_value: 0x0F, y_value: 0xF0, result: 0xFF, flag: FLAG_SENTINEL },
+        ArithmeticCase { opcode: 0x8122, x_value: 0x3C, y_value: 0x0F, result: 0x0C, flag: FLAG_SENTINEL },
+        ArithmeticCase { opcode: 0x8123, x_value: 0x3C, y_value: 0x0F, result: 0x33, flag: FLAG_SENTINEL },
+
+        // 8XY4: VF = carry
+        ArithmeticCase { opcode: 0x8124, x_value: 0x10, y_value: 0x20, result: 0x30, flag: 0 },
+        ArithmeticCase { opcode: 0x8124, x_value: 0xFF, y_value: 0x01, result: 0x00, flag: 1 },
+        ArithmeticCase { opcode: 0x8124, x_value: 0xFF, y_value: 0xFF, result: 0xFE, flag: 1 },
+
+        // 8XY5: VF = 1 when there is no borrow, including equal operands
+        ArithmeticCase { opcode: 0x8125, x_value: 0x30, y_value: 0x10, result: 0x20, flag: 1 },
+        ArithmeticCase { opcode: 0x8125, x_value: 0x10, y_value: 0x30, result: 0xE0, flag: 0 },
+        ArithmeticCase { opcode: 0x8125, x_value: 0x10, y_value: 0x10, result: 0x00, flag: 1 },
+
+        // 8XY6: Vx = Vy >> 1, VF = bit shifted out
+        ArithmeticCase { opcode: 0x8126, x_value: 0xFF, y_value: 0x05, result: 0x02, flag: 1 },
+        ArithmeticCase { opcode: 0x8126, x_value: 0xFF, y_value: 0x04, result: 0x02, flag: 0 },
+
+        // 8XY7: Vx = Vy - Vx, VF = 1 when there is no borrow
+        ArithmeticCase { opcode: 0x8127, x_value: 0x10, y_value: 0x30, result: 0x20, flag: 1 },
+        ArithmeticCase { opcode: 0x8127, x_value: 0x30, y_value: 0x10, result: 0xE0, flag: 0 },
+        ArithmeticCase { opcode: 0x8127, x_value: 0x10, y_value: 0x10, result: 0x00, flag: 1 },
+
+        // 8XYE: Vx = Vy << 1, VF = bit shifted out
+        ArithmeticCase { opcode: 0x812E, x_value: 0xFF, y_value: 0x81, result: 0x02, flag: 1 },
+        ArithmeticCase { opcode: 0x812E, x_value: 0xFF, y_value: 0x41, result: 0x82, flag: 0 },
+
+        // X is F: the flag overwrites the result
+        ArithmeticCase { opcode: 0x8F10, x_value: 0x12, y_value: 0x34, result: 0, flag: 0x34 },
+        ArithmeticCase { opcode: 0x8F11, x_value: 0x0F, y_value: 0xF0, result: 0, flag: 0xFF },
+        ArithmeticCase { opcode: 0x8F12, x_value: 0x3C, y_value: 0x0F, result: 0, flag: 0x0C },
+        ArithmeticCase { opcode: 0x8F13, x_value: 0x3C, y_value: 0x0F, result: 0, flag: 0x33 },
+        ArithmeticCase { opcode: 0x8F14, x_value: 0xFF, y_value: 0x01, result: 0, flag: 1 },
+        ArithmeticCase { opcode: 0x8F14, x_value: 0x10, y_value: 0x20, result: 0, flag: 0 },
+        ArithmeticCase { opcode: 0x8F15, x_value: 0x30, y_value: 0x10, result: 0, flag: 1 },
+        ArithmeticCase { opcode: 0x8F15, x_value: 0x10, y_value: 0x30, result: 0, flag: 0 },
+        ArithmeticCase { opcode: 0x8F06, x_value: 0x02, y_value: 0x03, result: 0, flag: 1 },
+        ArithmeticCase { opcode: 0x8F17, x_value: 0x30, y_value: 0x10, result: 0, flag: 0 },
+        ArithmeticCase { opcode: 0x8F0E, x_value: 0x80, y_value: 0x40, result: 0, flag: 0 },
+
+        // Y is F: the old VF is the operand, then VF becomes the flag
+        ArithmeticCase { opcode: 0x81F4, x_value: 0x10, y_value: 0x20, result: 0x30, flag: 0 },
+        ArithmeticCase { opcode: 0x81F5, x_value: 0x10, y_value: 0x20, result: 0xF0, flag: 0 },
+        ArithmeticCase { opcode: 0x81F7, x_value: 0x10, y_value: 0x20, result: 0x10, flag: 1 },
+        ArithmeticCase { opcode: 0x81F0, x_value: 0x10, y_value: 0x20, result: 0x20, flag: 0x20 },
+
+        // X and Y are both F
+        ArithmeticCase { opcode: 0x8FF4, x_value: 0x80, y_value: 0x80, result: 0, flag: 1 },
+        ArithmeticCase { opcode: 0x8FF5, x_value: 0x80, y_value: 0x80, result: 0, flag: 1 },
+    ];
+
+    // the shift-in-place quirk ignores Vy
+    const SHIFT_IN_PLACE_CASES: [ArithmeticCase; 6] = [
+        ArithmeticCase { opcode: 0x8126, x_value: 0x05, y_value: 0xFF, result: 0x02, flag: 1 },
+        ArithmeticCase { opcode: 0x8126, x_value: 0x04, y_value: 0xFF, result: 0x02, flag: 0 },
+        ArithmeticCase { opcode: 0x812E, x_value: 0x81, y_value: 0xFF, result: 0x02, flag: 1 },
+        ArithmeticCase { opcode: 0x812E, x_value: 0x41, y_value: 0xFF, result: 0x82, flag: 0 },
+        ArithmeticCase { opcode: 0x8F16, x_value: 0x03, y_value: 0x00, result: 0, flag: 1 },
+        ArithmeticCase { opcode: 0x8F1E, x_value: 0x40, y_value: 0xFF, result: 0, flag: 0 },
+    ];
+
+    fn run_arithmetic_case(case: &ArithmeticCase, quirks: Quirks) -> CPU {
+        let x = ((case.opcode & 0x0F00) >> 8) as usize;
+        let y = ((case.opcode & 0x00F0) >> 4) as usize;
+        let mut cpu = CPU::new();
+        cpu.quirks = quirks;
+        cpu.load_rom(case.opcode.to_be_bytes().to_vec()).unwrap();
+        cpu.registers[0xF] = FLAG_SENTINEL;
+        cpu.registers[x] = case.x_value;
+        cpu.registers[y] = case.y_value;
+        cpu.step();
+        cpu
+    }
+
+    fn check_arithmetic_cases(cases: &[ArithmeticCase], quirks: Quirks) {
+        for case in cases.iter() {
+            let x = ((case.opcode & 0x0F00) >> 8) as usize;
+            let cpu = run_arithmetic_case(case, quirks);
+
+            if x != 0xF {
+                assert!(cpu.registers[x] == case.result, "{:#06x}: V{:X} = {:#04x}, expected {:#04x}", case.opcode, x, cpu.registers[x], case.result);
+            }
+            assert!(cpu.registers[0xF] == case.flag, "{:#06x}: VF = {:#04x}, expected {:#04x}", case.opcode, cpu.registers[0xF], case.flag);
+        }
+    }
+
+    #[test]
+    fn should_match_reference_results_for_every_8xyn_case() {
+        check_arithmetic_cases(&ARITHMETIC_CASES, Quirks::default());
+    }
+
+    #[test]
+    fn should_shift_vx_in_place_when_shift_in_place_quirk() {
+        check_arithmetic_cases(&SHIFT_IN_PLACE_CASES, Quirks::parse("shift-in-place").unwrap());
+        assert!(Platform::Schip.quirks().shift_in_place && !Platform::Vip.quirks().shift_in_place);
+    }
+
+    #[test]
+    fn should_cover_every_8xyn_variant() {
+        for variant in [0x0, 0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0xE] {
+            assert!(ARITHMETIC_CASES.iter().any(|case| case.opcode & 0x000F == variant && case.opcode & 0x0F00 != 0x0F00));
+            assert!(ARITHMETIC_CASES.iter().any(|case| case.opcode & 0x000F == variant && case.opcode & 0x0F00 == 0x0F00));
+        }
+    }
+}
@@ -0,0 +1,523 @@
+pub mod debugger {
+
+    use std::collections::BTreeSet;
+
+    use crate::cpu::cpu::CPU;
+    use crate::symbols::symbols::SymbolTable;
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const MEMORY_BYTES_PER_ROW: usize = 8;
+    const STACK_WARNING_FRAMES    : usize = 2; // free frames left when the call stack panel starts warning
+    pub const MAX_SPRITE_HEIGHT   : usize = 15;
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    // panels that can be shown in the bottom right region of the debug view
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum DebugPanel {
+        IRegister,
+        Sprites,
+        Memory,
+        CallStack,
+    }
+
+    impl DebugPanel {
+        pub fn next(self) -> DebugPanel {
+            match self {
+                DebugPanel::IRegister => DebugPanel::Sprites,
+                DebugPanel::Sprites => DebugPanel::Memory,
+                DebugPanel::Memory => DebugPanel::CallStack,
+                DebugPanel::CallStack => DebugPanel::IRegister,
+            }
+        }
+    }
+
+    // how the sprite viewer reads bytes: one byte per 8 pixel row as drawn by DXYN,
+    // or two bytes per row for the 16x16 SUPER-CHIP sprites drawn by DXY0
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum SpriteMode {
+        Narrow,
+        Large,
+    }
+
+    impl SpriteMode {
+        pub fn toggled(self) -> SpriteMode {
+            match self {
+                SpriteMode::Narrow => SpriteMode::Large,
+                SpriteMode::Large => SpriteMode::Narrow,
+            }
+        }
+    }
+
+    // rows of pixels of the sprite at addr, reads wrap around the end of memory
+    pub fn decode_sprite(memory: &[u8], addr: usize, mode: SpriteMode, height: usize) -> Vec<Vec<bool>> {
+        let (rows, bytes_per_row) = match mode {
+            SpriteMode::Narrow => (height, 1),
+            SpriteMode::Large => (16, 2),
+        };
+        (0..rows)
+            .map(|row| {
+                (0..bytes_per_row * 8)
+                    .map(|col| {
+                        let byte = memory[(addr + row * bytes_per_row + col / 8) % memory.len()];
+                        byte & (0x80 >> (col % 8)) != 0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // one active subroutine call, read back from the stack and the calling instruction
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct CallFrame {
+        pub call_site: usize,   // address of the 2NNN that made the call
+        pub return_addr: usize, // where 00EE resumes
+        pub entry: usize,       // subroutine that was called
+    }
+
+    // active call frames, innermost first
+    pub fn call_frames(cpu: &CPU) -> Vec<CallFrame> {
+        cpu.stack[..cpu.sp.min(cpu.stack.len())]
+            .iter()
+            .rev()
+            .map(|call_site| {
+                let opcode = cpu.memory.get(*call_site..*call_site + 2)
+                    .map(|bytes| ((bytes[0] as usize) << 8) | bytes[1] as usize)
+                    .unwrap_or(0);
+                CallFrame { call_site: *call_site, return_addr: call_site + 2, entry: opcode & 0x0FFF }
+            })
+            .collect()
+    }
+
+    // true once only a couple of free stack slots are left
+    pub fn stack_overflow_risk(cpu: &CPU) -> bool {
+        cpu.sp + STACK_WARNING_FRAMES >= cpu.stack.len()
+    }
+
+    // a CPU value that can be edited from the debug view
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum EditTarget {
+        Register(usize),
+        I,
+        Pc,
+        Sp,
+        DelayTimer,
+        SoundTimer,
+        Memory(usize),
+    }
+
+    impl EditTarget {
+
+        pub fn label(&self) -> String {
+            match self {
+                EditTarget::Register(idx) => format!("V{:X}", idx),
+                EditTarget::I => "I ".to_string(),
+                EditTarget::Pc => "PC".to_string(),
+                EditTarget::Sp => "SP".to_string(),
+                EditTarget::DelayTimer => "DT".to_string(),
+                EditTarget::SoundTimer => "ST".to_string(),
+                EditTarget::Memory(addr) => format!("{:03x}", addr),
+            }
+        }
+
+        pub fn read(&self, cpu: &CPU) -> usize {
+            match self {
+                EditTarget::Register(idx) => cpu.registers[*idx] as usize,
+                EditTarget::I => cpu.reg_i,
+                EditTarget::Pc => cpu.pc,
+                EditTarget::Sp => cpu.sp,
+                EditTarget::DelayTimer => cpu.delay_timer as usize,
+                EditTarget::SoundTimer => cpu.sound_timer as usize,
+                EditTarget::Memory(addr) => cpu.memory[*addr] as usize,
+            }
+        }
+
+        // number of hex digits accepted while typing a value, enough for the largest one
+        pub fn max_digits(&self, cpu: &CPU) -> usize {
+            format!("{:x}", self.max_value(cpu)).len().max(2)
+        }
+
+        // largest value the target can hold, the PC has to leave room to fetch both opcode bytes
+        fn max_value(&self, cpu: &CPU) -> usize {
+            match self {
+                EditTarget::I => cpu.memory.len() - 1,
+                EditTarget::Pc => cpu.memory.len() - 2,
+                EditTarget::Sp => cpu.stack.len(),
+                _ => u8::MAX as usize,
+            }
+        }
+
+        pub fn write(&self, cpu: &mut CPU, value: usize) -> Result<(), String> {
+            if value > self.max_value(cpu) {
+                return Err(format!("{:#x} does not fit in {}", value, self.label().trim()));
+            }
+            match self {
+                EditTarget::Register(idx) => cpu.registers[*idx] = value as u8,
+                EditTarget::I => cpu.reg_i = value,
+                EditTarget::Pc => cpu.pc = value,
+                EditTarget::Sp => cpu.sp = value,
+                EditTarget::DelayTimer => cpu.delay_timer = value as u8,
+                EditTarget::SoundTimer => cpu.sound_timer = value as u8,
+                EditTarget::Memory(addr) => cpu.memory[*addr] = value as u8,
+            }
+            Ok(())
+        }
+    }
+
+    // value being typed for an edit target
+    pub struct EditSession {
+        pub target: EditTarget,
+        pub input: String,
+    }
+
+    // state of the debug view that lives outside of the CPU
+    pub struct DebugState {
+        pub panel: DebugPanel,
+        pub memory_scroll: usize,         // first memory row shown in the memory panel
+        pub goto_input: Option<String>,   // hex address being typed for a memory jump
+        pub edit: Option<EditSession>,
+        pub symbols: SymbolTable,
+        pub breakpoints: BTreeSet<usize>,
+        pub disasm_center: Option<usize>, // address the disassembly is centered on, None follows the PC
+        resume_from: Option<usize>,       // breakpoint to step over when resuming
+        pub sprite_addr: Option<usize>,   // address shown in the sprite viewer, None follows I
+        pub sprite_mode: SpriteMode,
+        pub sprite_height: usize,         // rows shown for narrow sprites
+        last_edit: Option<(EditTarget, usize)>, // target and value before the last applied edit
+        previous_memory: Vec<u8>,
+        changed_memory: Vec<bool>,
+    }
+
+    impl Default for DebugState {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl DebugState {
+
+        pub fn new() -> Self {
+            DebugState {
+                panel: DebugPanel::IRegister,
+                memory_scroll: 0,
+                goto_input: None,
+                edit: None,
+                symbols: SymbolTable::default(),
+                breakpoints: BTreeSet::new(),
+                disasm_center: None,
+                resume_from: None,
+                sprite_addr: None,
+                sprite_mode: SpriteMode::Narrow,
+                sprite_height: MAX_SPRITE_HEIGHT,
+                last_edit: None,
+                previous_memory: Vec::new(),
+                changed_memory: Vec::new(),
+            }
+        }
+
+        // forget state tied to the old ROM after it was reloaded, breakpoints can be kept
+        // when the program only changed a little
+        pub fn reset_for_reload(&mut self, keep_breakpoints: bool) {
+            if !keep_breakpoints {
+                self.breakpoints.clear();
+            }
+            self.goto_input = None;
+            self.edit = None;
+            self.last_edit = None;
+            self.resume_from = None;
+            self.disasm_center = None;
+            self.sprite_addr = None;
+            self.previous_memory.clear();
+            self.changed_memory.clear();
+        }
+
+        pub fn start_edit(&mut self, target: EditTarget) {
+            self.edit = Some(EditSession { target, input: String::new() });
+        }
+
+        // write the typed hex value to the CPU, remembering the old value for undo
+        pub fn submit_edit(&mut self, cpu: &mut CPU) -> Result<(), String> {
+            let session = match self.edit.take() {
+                Some(session) => session,
+                None => return Ok(()),
+            };
+            let value = usize::from_str_radix(&session.input, 16)
+                .map_err(|_| format!("invalid hex value: {:?}", session.input))?;
+            let old_value = session.target.read(cpu);
+            session.target.write(cpu, value)?;
+            self.last_edit = Some((session.target, old_value));
+            Ok(())
+        }
+
+        // restore the value overwritten by the last edit, returns the restored target
+        pub fn undo_edit(&mut self, cpu: &mut CPU) -> Option<EditTarget> {
+            let (target, old_value) = self.last_edit.take()?;
+            target.write(cpu, old_value).ok()?;
+            Some(target)
+        }
+
+        // returns true if a breakpoint was added, false if one was removed
+        pub fn toggle_breakpoint(&mut self, addr: usize) -> bool {
+            if self.breakpoints.remove(&addr) {
+                return false;
+            }
+            self.breakpoints.insert(addr)
+        }
+
+        // call when execution is resumed so the breakpoint at pc does not fire again right away
+        pub fn resume(&mut self, pc: usize) {
+            self.resume_from = Some(pc);
+        }
+
+        // check before running the instruction at pc
+        pub fn should_break(&mut self, pc: usize) -> bool {
+            if self.resume_from.take() == Some(pc) {
+                return false;
+            }
+            self.breakpoints.contains(&pc)
+        }
+
+        // address the disassembly listing is centered on
+        pub fn disasm_center(&self, pc: usize) -> usize {
+            self.disasm_center.unwrap_or(pc)
+        }
+
+        // move the disassembly by whole instructions, detaching it from the PC
+        pub fn scroll_disasm(&mut self, instructions: isize, pc: usize, memory_len: usize) {
+            let center = self.disasm_center(pc)
+                .saturating_add_signed(instructions * 2)
+                .min(memory_len.saturating_sub(2));
+            self.disasm_center = Some(center);
+        }
+
+        // compare memory against the last frame, call once per frame
+        pub fn track_memory_changes(&mut self, memory: &[u8]) {
+            if self.previous_memory.len() != memory.len() {
+                self.previous_memory = memory.to_vec();
+                self.changed_memory = vec![false; memory.len()];
+                return;
+            }
+            for (idx, byte) in memory.iter().enumerate() {
+                self.changed_memory[idx] = self.previous_memory[idx] != *byte;
+            }
+            self.previous_memory.copy_from_slice(memory);
+        }
+
+        // true if the byte was written with a new value since the last frame
+        pub fn is_changed(&self, addr: usize) -> bool {
+            self.changed_memory.get(addr).copied().unwrap_or(false)
+        }
+
+        pub fn scroll_memory(&mut self, rows: isize, memory_len: usize) {
+            let last_row = (memory_len / MEMORY_BYTES_PER_ROW).saturating_sub(1);
+            self.memory_scroll = self.memory_scroll
+                .saturating_add_signed(rows)
+                .min(last_row);
+        }
+
+        // scroll so the row holding addr is at the top of the panel
+        pub fn jump_to(&mut self, addr: usize, memory_len: usize) {
+            self.memory_scroll = 0;
+            self.scroll_memory((addr / MEMORY_BYTES_PER_ROW) as isize, memory_len);
+        }
+
+        pub fn change_sprite_height(&mut self, rows: isize) {
+            self.sprite_height = self.sprite_height.saturating_add_signed(rows).clamp(1, MAX_SPRITE_HEIGHT);
+        }
+
+        // apply the typed goto address to the panel that is shown
+        pub fn submit_goto(&mut self, memory_len: usize) -> Result<(), String> {
+            let input = self.goto_input.take().unwrap_or_default();
+            match usize::from_str_radix(&input, 16) {
+                Ok(addr) if addr < memory_len => {
+                    match self.panel {
+                        DebugPanel::Sprites => self.sprite_addr = Some(addr),
+                        _ => self.jump_to(addr, memory_len),
+                    }
+                    Ok(())
+                },
+                _ => Err(format!("invalid memory address: {:?}", input)),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::debugger::{call_frames, decode_sprite, stack_overflow_risk, DebugPanel, DebugState, EditTarget, SpriteMode, MEMORY_BYTES_PER_ROW};
+    use crate::cpu::cpu::CPU;
+
+    #[test]
+    fn should_flag_bytes_written_since_last_frame() {
+        let mut state = DebugState::new();
+        let mut memory = vec![0u8; 0x1000];
+        state.track_memory_changes(&memory);
+
+        memory[0x300] = 0xAB;
+        state.track_memory_changes(&memory);
+        assert!(state.is_changed(0x300));
+        assert!(!state.is_changed(0x301));
+
+        state.track_memory_changes(&memory);
+        assert!(!state.is_changed(0x300));
+    }
+
+    #[test]
+    fn should_jump_to_row_of_typed_address_and_clamp_scrolling() {
+        let mut state = DebugState::new();
+
+        state.goto_input = Some("2a4".to_string());
+        assert!(state.submit_goto(0x1000).is_ok());
+        assert!(state.memory_scroll == 0x2a4 / MEMORY_BYTES_PER_ROW);
+
+        state.goto_input = Some("1000".to_string());
+        assert!(state.submit_goto(0x1000).is_err());
+
+        state.scroll_memory(10_000, 0x1000);
+        assert!(state.memory_scroll == 0x1000 / MEMORY_BYTES_PER_ROW - 1);
+        state.scroll_memory(-10_000, 0x1000);
+        assert!(state.memory_scroll == 0);
+    }
+
+    #[test]
+    fn should_write_typed_value_and_undo_last_edit() {
+        let mut state = DebugState::new();
+        let mut cpu = CPU::new();
+        cpu.registers[3] = 0x12;
+
+        state.start_edit(EditTarget::Register(3));
+        state.edit.as_mut().unwrap().input = "ab".to_string();
+        assert!(state.submit_edit(&mut cpu).is_ok());
+        assert!(cpu.registers[3] == 0xAB);
+
+        assert!(state.undo_edit(&mut cpu) == Some(EditTarget::Register(3)));
+        assert!(cpu.registers[3] == 0x12);
+        assert!(state.undo_edit(&mut cpu).is_none());
+    }
+
+    #[test]
+    fn should_reject_values_that_do_not_fit_the_target() {
+        let mut state = DebugState::new();
+        let mut cpu = CPU::new();
+
+        state.start_edit(EditTarget::Memory(0x300));
+        state.edit.as_mut().unwrap().input = "100".to_string();
+        assert!(state.submit_edit(&mut cpu).is_err());
+
+        state.start_edit(EditTarget::Pc);
+        state.edit.as_mut().unwrap().input = "xyz".to_string();
+        assert!(state.submit_edit(&mut cpu).is_err());
+        state.start_edit(EditTarget::Pc);
+        state.edit.as_mut().unwrap().input = "fff".to_string();
+        assert!(state.submit_edit(&mut cpu).is_err());
+        assert!(cpu.pc == 0x200);
+
+        // I takes a fourth digit once memory is larger than 4 KiB
+        assert!(EditTarget::I.max_digits(&cpu) == 3);
+        assert!(EditTarget::I.max_digits(&CPU::with_limits(0x10000, 12)) == 4);
+    }
+
+    #[test]
+    fn should_list_call_frames_innermost_first() {
+        let mut cpu = CPU::new();
+        // 0x200: call 0x300, 0x300: call 0x310
+        cpu.load_rom(vec![0x23, 0x00]).unwrap();
+        cpu.memory[0x300] = 0x23;
+        cpu.memory[0x301] = 0x10;
+        cpu.step();
+        cpu.step();
+
+        let frames = call_frames(&cpu);
+        assert!(frames.len() == 2);
+        assert!(frames[0].call_site == 0x300 && frames[0].entry == 0x310 && frames[0].return_addr == 0x302);
+        assert!(frames[1].call_site == 0x200 && frames[1].entry == 0x300 && frames[1].return_addr == 0x202);
+    }
+
+    #[test]
+    fn should_warn_when_stack_is_nearly_full() {
+        let mut cpu = CPU::new();
+        assert!(!stack_overflow_risk(&cpu));
+
+        cpu.sp = cpu.stack.len() - 2;
+        assert!(stack_overflow_risk(&cpu));
+    }
+
+    #[test]
+    fn should_break_on_breakpoint_but_step_over_it_when_resuming() {
+        let mut state = DebugState::new();
+        assert!(state.toggle_breakpoint(0x204));
+        assert!(!state.should_break(0x202));
+        assert!(state.should_break(0x204));
+
+        state.resume(0x204);
+        assert!(!state.should_break(0x204));
+        assert!(state.should_break(0x204));
+
+        assert!(!state.toggle_breakpoint(0x204));
+        assert!(!state.should_break(0x204));
+    }
+
+    #[test]
+    fn should_scroll_disassembly_independently_of_pc() {
+        let mut state = DebugState::new();
+        assert!(state.disasm_center(0x200) == 0x200);
+
+        state.scroll_disasm(3, 0x200, 0x1000);
+        assert!(state.disasm_center(0x300) == 0x206);
+        state.scroll_disasm(-10_000, 0x200, 0x1000);
+        assert!(state.disasm_center(0x300) == 0);
+
+        state.disasm_center = None;
+        assert!(state.disasm_center(0x300) == 0x300);
+    }
+
+    #[test]
+    fn should_decode_narrow_and_large_sprites() {
+        let mut memory = vec![0u8; 0x1000];
+        memory[0x300..0x304].copy_from_slice(&[0x81, 0x42, 0xFF, 0x00]);
+
+        let narrow = decode_sprite(&memory, 0x300, SpriteMode::Narrow, 2);
+        assert!(narrow.len() == 2 && narrow[0].len() == 8);
+        assert!(narrow[0] == [true, false, false, false, false, false, false, true]);
+        assert!(narrow[1] == [false, true, false, false, false, false, true, false]);
+
+        let large = decode_sprite(&memory, 0x300, SpriteMode::Large, 0);
+        assert!(large.len() == 16 && large[0].len() == 16);
+        assert!(large[0][0] && large[0][7] && large[0][9] && !large[0][8]);
+        assert!(large[1][0] && !large[1][8]);
+    }
+
+    #[test]
+    fn should_send_goto_to_sprite_viewer_when_it_is_shown() {
+        let mut state = DebugState::new();
+        state.panel = DebugPanel::Sprites;
+
+        state.goto_input = Some("2a4".to_string());
+        assert!(state.submit_goto(0x1000).is_ok());
+        assert!(state.sprite_addr == Some(0x2a4));
+        assert!(state.memory_scroll == 0);
+
+        state.change_sprite_height(-100);
+        assert!(state.sprite_height == 1);
+    }
+
+    #[test]
+    fn should_keep_breakpoints_only_when_asked_on_reload() {
+        let mut state = DebugState::new();
+        state.toggle_breakpoint(0x204);
+        state.disasm_center = Some(0x300);
+
+        state.reset_for_reload(true);
+        assert!(state.breakpoints.contains(&0x204));
+        assert!(state.disasm_center.is_none());
+
+        state.reset_for_reload(false);
+        assert!(state.breakpoints.is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+pub mod disasm {
+
+    use crate::symbols::symbols::SymbolTable;
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    // read the big-endian instruction word at addr, bytes past the end of memory read as 0
+    pub fn read_opcode(memory: &[u8], addr: usize) -> usize {
+        let high = memory.get(addr).copied().unwrap_or(0) as usize;
+        let low = memory.get(addr + 1).copied().unwrap_or(0) as usize;
+        (high << 8) | low
+    }
+
+    // address an instruction refers to, if any
+    pub fn target_address(opcode: usize) -> Option<usize> {
+        match opcode & 0xF000 {
+            0x0000 if opcode != 0x00E0 && opcode != 0x00EE => Some(opcode & 0x0FFF),
+            0x1000 | 0x2000 | 0xA000 | 0xB000 => Some(opcode & 0x0FFF),
+            _ => None,
+        }
+    }
+
+    // assembly text for an instruction using the common CHIP-8 mnemonics, addresses
+    // that have a label in the symbol table are shown by name
+    pub fn disassemble(opcode: usize, symbols: &SymbolTable) -> String {
+        let x = (opcode & 0x0F00) >> 8;
+        let y = (opcode & 0x00F0) >> 4;
+        let n = opcode & 0x000F;
+        let nn = opcode & 0x00FF;
+        let addr = match target_address(opcode) {
+            Some(target) => symbols.label(target)
+                .map(|label| label.to_string())
+                .unwrap_or(format!("x{:03x}", target)),
+            None => String::new(),
+        };
+
+        match (opcode & 0xF000, n, nn) {
+            (0x0000, _, 0xE0) if x == 0 => "CLS".to_string(),
+            (0x0000, _, 0xEE) if x == 0 => "RET".to_string(),
+            (0x0000, _, _) => format!("SYS  {}", addr),
+            (0x1000, _, _) => format!("JP   {}", addr),
+            (0x2000, _, _) => format!("CALL {}", addr),
+            (0x3000, _, _) => format!("SE   V{:X}, {:#04x}", x, nn),
+            (0x4000, _, _) => format!("SNE  V{:X}, {:#04x}", x, nn),
+            (0x5000, 0x0, _) => format!("SE   V{:X}, V{:X}", x, y),
+            (0x6000, _, _) => format!("LD   V{:X}, {:#04x}", x, nn),
+            (0x7000, _, _) => format!("ADD  V{:X}, {:#04x}", x, nn),
+            (0x8000, 0x0, _) => format!("LD   V{:X}, V{:X}", x, y),
+            (0x8000, 0x1, _) => format!("OR   V{:X}, V{:X}", x, y),
+            (0x8000, 0x2, _) => format!("AND  V{:X}, V{:X}", x, y),
+            (0x8000, 0x3, _) => format!("XOR  V{:X}, V{:X}", x, y),
+            (0x8000, 0x4, _) => format!("ADD  V{:X}, V{:X}", x, y),
+            (0x8000, 0x5, _) => format!("SUB  V{:X}, V{:X}", x, y),
+            (0x8000, 0x6, _) => format!("SHR  V{:X}, V{:X}", x, y),
+            (0x8000, 0x7, _) => format!("SUBN V{:X}, V{:X}", x, y),
+            (0x8000, 0xE, _) => format!("SHL  V{:X}, V{:X}", x, y),
+            (0x9000, 0x0, _) => format!("SNE  V{:X}, V{:X}", x, y),
+            (0xA000, _, _) => format!("LD   I, {}", addr),
+            (0xB000, _, _) => format!("JP   V0, {}", addr),
+            (0xC000, _, _) => format!("RND  V{:X}, {:#04x}", x, nn),
+            (0xD000, _, _) => format!("DRW  V{:X}, V{:X}, {}", x, y, n),
+            (0xE000, _, 0x9E) => format!("SKP  V{:X}", x),
+            (0xE000, _, 0xA1) => format!("SKNP V{:X}", x),
+            (0xF000, _, 0x00) if x == 0 => "LD   I, LONG".to_string(),
+            (0xF000, _, 0x07) => format!("LD   V{:X}, DT", x),
+            (0xF000, _, 0x0A) => format!("LD   V{:X}, K", x),
+            (0xF000, _, 0x15) => format!("LD   DT, V{:X}", x),
+            (0xF000, _, 0x18) => format!("LD   ST, V{:X}", x),
+            (0xF000, _, 0x1E) => format!("ADD  I, V{:X}", x),
+            (0xF000, _, 0x29) => format!("LD   F, V{:X}", x),
+            (0xF000, _, 0x33) => format!("LD   B, V{:X}", x),
+            (0xF000, _, 0x55) => format!("LD   [I], V{:X}", x),
+            (0xF000, _, 0x65) => format!("LD   V{:X}, [I]", x),
+            _ => format!("DW   {:#06x}", opcode),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::disasm::{disassemble, read_opcode, target_address};
+    use crate::symbols::symbols::SymbolTable;
+
+    #[test]
+    fn should_disassemble_common_instructions() {
+        let symbols = SymbolTable::default();
+
+        assert!(disassemble(0x00E0, &symbols) == "CLS");
+        assert!(disassemble(0x6A02, &symbols) == "LD   VA, 0x02");
+        assert!(disassemble(0x8125, &symbols) == "SUB  V1, V2");
+        assert!(disassemble(0xD015, &symbols) == "DRW  V0, V1, 5");
+        assert!(disassemble(0xF365, &symbols) == "LD   V3, [I]");
+        assert!(disassemble(0x2300, &symbols) == "CALL x300");
+        assert!(disassemble(0x5121, &symbols) == "DW   0x5121");
+    }
+
+    #[test]
+    fn should_resolve_jump_targets_to_labels() {
+        let symbols = SymbolTable::parse("loop = 0x2a4").unwrap();
+
+        assert!(disassemble(0x12A4, &symbols) == "JP   loop");
+        assert!(disassemble(0xB2A4, &symbols) == "JP   V0, loop");
+        assert!(target_address(0x6A02).is_none());
+        assert!(target_address(0xA2A4) == Some(0x2A4));
+    }
+
+    #[test]
+    fn should_read_opcode_and_pad_past_end_of_memory() {
+        let memory = [0x12, 0x34, 0x56];
+
+        assert!(read_opcode(&memory, 0) == 0x1234);
+        assert!(read_opcode(&memory, 2) == 0x5600);
+    }
+}
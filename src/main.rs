@@ -1,46 +1,115 @@
 mod cpu; 
-pub use cpu::cpu::{CPU, SCREEN_HEIGHT, SCREEN_WIDTH, Chip8Input, get_chip8_key_idx}; 
+pub use cpu::cpu::{CPU, SCREEN_HEIGHT, SCREEN_WIDTH, DEFAULT_MEMORY_SIZE, DEFAULT_STACK_DEPTH, FONT_BYTES, ROM_START_ADDR, Chip8Input, Font, FontSet, MachineCodeMode, MachineRoutine, Platform, Quirks, get_chip8_key_idx, parse_font, parse_routine}; 
 
 mod video;
 pub use video::video::{
     write_text,
     draw_entire_window, 
+    draw_rom_browser,
+    browser_entry_at,
+    browser_rows,
     draw_register_region, 
     draw_pc_region, 
     draw_i_region, 
+    draw_memory_region,
+    draw_call_stack_region,
+    draw_sprite_region,
+    edit_target_at,
+    disasm_addr_at,
+    keypad_key_at,
+    draw_keypad,
+    logical_size,
+    save_screenshot,
+    Palette,
+    Persistence,
+    Renderer,
+    ScaleMode,
     CANVAS_WIDTH, 
     CANVAS_HEIGHT,
     DEBUG_CANVAS_WIDTH, 
     DEBUG_CANVAS_HEIGHT
 };
 
+mod debugger;
+pub use debugger::debugger::{DebugPanel, DebugState, EditTarget};
+
+mod disasm;
+pub use disasm::disasm::{disassemble, read_opcode};
+
+mod symbols;
+pub use symbols::symbols::SymbolTable;
+
+mod scheduler;
+pub use scheduler::scheduler::{FrameBudget, FrameOutcome, FrameScheduler, DEFAULT_INSTRUCTIONS_PER_FRAME};
+
+mod timing;
+pub use timing::timing::{vip_cycles, VIP_CYCLES_PER_FRAME};
+
+mod rom;
+pub use rom::rom::{is_rom_path, read_rom_image, RomWatcher};
+
+mod cartridge;
+mod octo;
+
+mod browser;
+pub use browser::browser::{history_path, BrowserEntry, RomBrowser, RomHistory};
+
+mod postprocess;
+pub use postprocess::postprocess::CrtSettings;
+
 mod audio; 
-pub use audio::audio::SquareWave;
+pub use audio::audio::{Beeper, BeeperSettings, Waveform, WavRecorder, SAMPLE_RATE};
 
 extern crate sdl2;
 
-use regex::Regex;
 use sdl2::audio::AudioSpecDesired; 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode as SdlKeycode;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::EventPump;
+use sdl2::keyboard::{Keycode as SdlKeycode, Mod};
+use sdl2::mouse::MouseButton;
 use sdl2::render::Canvas;
-use sdl2::video::Window; 
+use sdl2::rwops::RWops;
+use sdl2::video::{FullscreenType, Window};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env; 
 use std::fs;
-use std::fs::File;  
-use std::io::Read; 
-use std::path::Path; 
-use std::process; 
-use std::time::Instant;
+use std::path::{Path, PathBuf}; 
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
  
-const OSA_SCRIPTS_PATH: &str = "/Users/nicktrueb/.osascripts";
-const FONT_PATH: &str = "/Users/nicktrueb/Programming/chip8/assets/FragmentMono-Regular.ttf";
+const MEMORY_PAGE_ROWS: isize = 8;
+const UI_FONT: &[u8] = include_bytes!("../assets/FragmentMono-Regular.ttf"); // built in so the binary runs from anywhere
 
 #[derive(Eq, PartialEq, Hash, Debug)]
-enum OptionalModes { 
-    Debug, 
+enum OptionalModes {
+    Debug,
+}
+
+// settings parsed from the command line
+struct Config {
+    filename: Option<String>,
+    modes: HashSet<OptionalModes>,
+    wav_path: Option<String>,
+    beeper: BeeperSettings,
+    scale_mode: ScaleMode,
+    fullscreen: bool,
+    palette: Palette,
+    persistence: Persistence,
+    crt: CrtSettings,
+    symbols: SymbolTable,
+    keypad: bool,
+    quirks: Option<Quirks>,
+    budget: FrameBudget,
+    machine_code: MachineCodeMode,
+    routines: Vec<(usize, MachineRoutine)>,
+    platform: Option<Platform>,
+    font: Option<Font>,
+    font_base: Option<usize>,
+    stack_depth: Option<usize>,
+    memory_size: Option<usize>,
+    watch: bool,
+    keep_breakpoints: bool,
+    autostart: bool,
 }
 
 // maps Keycode value (from sdl2) to Chip8Input value (0-F) to simulate controller input 
@@ -72,7 +141,7 @@ fn build_keycode_hashmap() -> HashMap<SdlKeycode, Chip8Input>{
     ])
 }
 
-fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), String> { 
+fn execute(cpu: Option<CPU>, config: &mut Config) -> Result<(), String> {
 
     // initialize contexts 
     let sdl_context = 
@@ -89,19 +158,24 @@ fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), Strin
             .expect("ERROR:: failed to load ttf context"); 
 
     // get initial window instance
-    let (width, height) = match modes.contains(&OptionalModes::Debug) { 
-        true => { (DEBUG_CANVAS_WIDTH as u32, DEBUG_CANVAS_HEIGHT as u32) },
-        false => { (CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32) }
-    }; 
-    let init_window: Window = video_subsystem
+    let (width, height) = logical_size(config.modes.contains(&OptionalModes::Debug));
+    let mut init_window: Window = video_subsystem
         .window("CHIP-8", width, height)
         .position_centered()
+        .resizable()
         .build()
         .map_err(|e| e.to_string())?; 
+    init_window
+        .set_minimum_size(SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
+        .map_err(|e| e.to_string())?;
+    if config.fullscreen {
+        init_window.set_fullscreen(FullscreenType::Desktop)?;
+    }
 
-    // get canvas instance
+    // get canvas instance, the software renderer scales the display texture so no GPU is needed
     let mut canvas: Canvas<Window> = init_window
         .into_canvas()
+        .software()
         .target_texture()
         .present_vsync()
         .build()
@@ -109,42 +183,69 @@ fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), Strin
 
     // initialize desired audio spec and open actual audio spec
     let desired_spec = AudioSpecDesired { 
-        freq: Some(44100), 
+        freq: Some(SAMPLE_RATE), 
         channels: Some(1), 
         samples: None
     };
 
-    let audio_device = 
+    let mut audio_device = 
         audio_subsystem.open_playback(
             None, 
             &desired_spec, 
-            |spec| { 
-                let phase_inc = 261.0 / spec.freq as f32; 
-                SquareWave { 
-                    phase: 0.0, 
-                    phase_inc,
-                    amplitude: 0.1,
-                }
-            }
-        ).expect("ERROR:: failed to initialize sound struct"); 
+            |spec| Beeper::new(config.beeper, spec.freq)
+        ).expect("ERROR:: failed to initialize sound struct");
+
+    // the device keeps running and the beeper's gate follows the sound timer, so
+    // the envelope can fade the tone in and out instead of clicking
+    audio_device.resume();
+
+    // render audio offline as well when a wav file was requested
+    let mut wav_recorder: Option<WavRecorder> = config.wav_path
+        .as_ref()
+        .map(|_| WavRecorder::new(config.beeper, SAMPLE_RATE));
 
     // load font 
     let point_size = 18; 
-    let font = ttf_context.load_font(Path::new(FONT_PATH), point_size)
-        .expect("ERROR: failed to load external font"); 
+    let font = ttf_context.load_font_from_rwops(RWops::from_bytes(UI_FONT)?, point_size)
+        .expect("ERROR: failed to load built-in font"); 
+
+    // create long-lived textures for the display and text glyphs
+    let texture_creator = canvas.texture_creator();
+    let mut renderer = Renderer::new(&texture_creator, &font, config.scale_mode, config.palette, config.persistence, config.crt)?;
+    renderer.keypad.visible = config.keypad;
+    let mut event_pump = sdl_context.event_pump()?;
+
+    // pick a rom in the window when none was given on the command line
+    let mut cpu = match cpu {
+        Some(cpu) => cpu,
+        None => match browse_for_rom(config, &mut canvas, &mut renderer, &mut event_pump)? {
+            Some((cpu, filename)) => {
+                config.filename = Some(filename);
+                cpu
+            },
+            None => return Ok(()),
+        },
+    };
+    let modes = &mut config.modes;
+
+    if modes.contains(&OptionalModes::Debug) {
+        println!("{}", cpu.dump_memory()); 
+        println!("Starting CPU with the following modes: {:?}", modes);
+    }  
 
     // reset canvas and update window
     let mut paused_state = true; 
-    draw_entire_window(&mut canvas, &cpu, &font, modes.contains(&OptionalModes::Debug), paused_state); 
+    let mut debug_state = DebugState::new();
+    debug_state.symbols = std::mem::take(&mut config.symbols);
+    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state); 
 
-    let mut event_pump = sdl_context.event_pump()?;
     let mut manual_step_signal: bool = false; 
-    let mut prev_cpu_cycle_time: Instant = Instant::now(); 
-    let mut prev_screen_refresh_time: Instant = Instant::now();
-    let mut timer_refresh_time: Instant = Instant::now(); 
-    let cpu_cycle_freq:u128 = 60;  
-    let screen_refresh_freq: u128 = 60; 
-    let timer_update_freq: u128 = 60; 
+    let mut clicked_key: Option<usize> = None; 
+    let mut scheduler = FrameScheduler::new(config.budget, Instant::now()); 
+    let mut rom_watcher: Option<RomWatcher> = match (config.watch, config.filename.as_ref()) {
+        (true, Some(filename)) => Some(RomWatcher::new(Path::new(filename), Instant::now())),
+        _ => None,
+    };
     let keyboard_to_chip8_input_map: HashMap<SdlKeycode, Chip8Input> = build_keycode_hashmap(); 
 
     // enter main game loop 
@@ -152,25 +253,238 @@ fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), Strin
 
         // event handling
         for event in event_pump.poll_event() {
+
+            // releases always reach the CPU so no key stays held while a debugger prompt has focus
+            if let Event::KeyUp { keycode: Some(key), .. } = event {
+                if let Some(input) = keyboard_to_chip8_input_map.get(&key) {
+                    cpu.key_up(get_chip8_key_idx(input));
+                }
+            }
+
             match event {
                 Event::Quit { .. } => break 'running, 
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::TextInput { text, .. } if debug_state.goto_input.is_some() => {
+                    if let Some(input) = debug_state.goto_input.as_mut() {
+                        input.extend(text.chars().filter(|ch| ch.is_ascii_hexdigit()));
+                        input.truncate(4);
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::TextInput { text, .. } if debug_state.edit.is_some() => {
+                    if let Some(edit) = debug_state.edit.as_mut() {
+                        edit.input.extend(text.chars().filter(|ch| ch.is_ascii_hexdigit()));
+                        edit.input.truncate(edit.target.max_digits(&cpu));
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let debug = modes.contains(&OptionalModes::Debug);
+                    let (logical_x, logical_y) = renderer.viewport(&canvas, debug).to_logical(x, y);
+
+                    // a key on the keypad overlay stays pressed until the button is released
+                    if renderer.keypad.visible {
+                        clicked_key = keypad_key_at(logical_x, logical_y);
+                        if let Some(key) = clicked_key { cpu.key_down(key); }
+                    }
+
+                    if debug {
+
+                        // clicking a disassembly row toggles its breakpoint
+                        let disasm_center = debug_state.disasm_center(cpu.pc);
+                        if let Some(addr) = disasm_addr_at(logical_x, logical_y, renderer.line_height(), disasm_center, cpu.memory.len()) {
+                            debug_state.toggle_breakpoint(addr);
+                        }
+
+                        // while paused, clicking a register or memory byte starts editing it, clicking anything else cancels
+                        if paused_state {
+                            debug_state.edit = None;
+                            if let Some(target) = edit_target_at(logical_x, logical_y, renderer.line_height(), renderer.char_width(), &debug_state, cpu.memory.len()) {
+                                debug_state.start_edit(target);
+                            }
+                        }
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } => {
+                    if let Some(key) = clicked_key.take() { cpu.key_up(key); }
+                },
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } if debug_state.goto_input.is_none() && debug_state.edit.is_none() => {
+                    if let Some(input) = keyboard_to_chip8_input_map.get(&key) {
+                        cpu.key_down(get_chip8_key_idx(input));
+                    }
+                },
+                Event::MouseWheel { y, .. } if modes.contains(&OptionalModes::Debug) => {
+
+                    // scroll whichever panel the mouse is over
+                    let mouse = event_pump.mouse_state();
+                    let (logical_x, logical_y) = renderer.viewport(&canvas, true).to_logical(mouse.x(), mouse.y());
+                    if logical_x < CANVAS_WIDTH as i32 && logical_y >= CANVAS_HEIGHT as i32 {
+                        debug_state.scroll_disasm(-y as isize, cpu.pc, cpu.memory.len());
+                    } else if debug_state.panel == DebugPanel::Memory {
+                        debug_state.scroll_memory(-y as isize, cpu.memory.len());
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
                 Event::KeyUp {
                     keycode: Some(key),
                     ..
+                } if debug_state.goto_input.is_some() => {
+
+                    // typing a memory address, keys edit the prompt instead of controlling the emulator
+                    match key {
+                        SdlKeycode::Return | SdlKeycode::KpEnter => {
+                            debug_state.submit_goto(cpu.memory.len()).unwrap_or_else(|e| println!("{}", e));
+                        },
+                        SdlKeycode::Escape => { debug_state.goto_input = None; },
+                        SdlKeycode::Backspace => {
+                            if let Some(input) = debug_state.goto_input.as_mut() { input.pop(); }
+                        },
+                        _ => {},
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::KeyUp {
+                    keycode: Some(key),
+                    ..
+                } if debug_state.edit.is_some() => {
+
+                    // typing a new value, keys edit the value instead of controlling the emulator
+                    match key {
+                        SdlKeycode::Return | SdlKeycode::KpEnter => {
+                            debug_state.submit_edit(&mut cpu).unwrap_or_else(|e| println!("{}", e));
+                        },
+                        SdlKeycode::Escape => { debug_state.edit = None; },
+                        SdlKeycode::Backspace => {
+                            if let Some(edit) = debug_state.edit.as_mut() { edit.input.pop(); }
+                        },
+                        _ => {},
+                    }
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Event::KeyUp {
+                    keycode: Some(key),
+                    keymod,
+                    ..
                 } => { 
                     match key {
+                        SdlKeycode::Z if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                            match debug_state.undo_edit(&mut cpu) {
+                                Some(target) => println!("undid edit of {}", target.label().trim()),
+                                None => println!("nothing to undo"),
+                            }
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
                         SdlKeycode::Escape => {
                             cpu.reset(); 
                             paused_state = true;
-                            draw_entire_window(&mut canvas, &cpu, &font, modes.contains(&OptionalModes::Debug), paused_state); 
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state); 
                         }, 
                         SdlKeycode::Space => {
                             paused_state = !paused_state; 
-                            draw_entire_window(&mut canvas, &cpu, &font, modes.contains(&OptionalModes::Debug), paused_state);
+                            if !paused_state { debug_state.resume(cpu.pc); }
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::M => {
+                            let muted = audio_device.lock().toggle_mute();
+                            println!("audio {}", if muted { "muted" } else { "unmuted" });
                         },
                         SdlKeycode::RShift => {
                             if paused_state { manual_step_signal = true; }
                         },
+                        SdlKeycode::P => {
+                            renderer.palette = renderer.palette.next();
+                            println!("palette: {}", renderer.palette.name);
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::O => {
+                            renderer.display_filter.persistence = renderer.display_filter.persistence.next();
+                            println!("persistence: {:?}", renderer.display_filter.persistence);
+                        },
+                        SdlKeycode::F12 => {
+                            let timestamp = SystemTime::now()
+                                .duration_since(UNIX_EPOCH)
+                                .map(|duration| duration.as_secs())
+                                .unwrap_or(0);
+                            let screenshot_path = format!("chip8-screenshot-{}.png", timestamp);
+                            match save_screenshot(Path::new(&screenshot_path), renderer.display_filter.intensities(), &renderer.palette, &renderer.crt) {
+                                Ok(()) => println!("saved screenshot to {}", screenshot_path),
+                                Err(e) => println!("ERROR:: failed to save screenshot: {}", e),
+                            }
+                        },
+                        SdlKeycode::F9 => {
+                            renderer.crt.enabled = !renderer.crt.enabled;
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::Tab => {
+                            debug_state.panel = debug_state.panel.next();
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::G if matches!(debug_state.panel, DebugPanel::Memory | DebugPanel::Sprites) => {
+                            debug_state.goto_input = Some(String::new());
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::K => {
+                            renderer.keypad.visible = !renderer.keypad.visible;
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::B => {
+                            let added = debug_state.toggle_breakpoint(cpu.pc);
+                            println!("breakpoint {} at x{:03x}", if added { "set" } else { "cleared" }, cpu.pc);
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::I if debug_state.panel == DebugPanel::Sprites => {
+                            debug_state.sprite_addr = None;
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::L if debug_state.panel == DebugPanel::Sprites => {
+                            debug_state.sprite_mode = debug_state.sprite_mode.toggled();
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::Minus | SdlKeycode::Equals if debug_state.panel == DebugPanel::Sprites => {
+                            debug_state.change_sprite_height(if key == SdlKeycode::Minus { -1 } else { 1 });
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::Up | SdlKeycode::Down => {
+                            let instructions = if key == SdlKeycode::Up { -1 } else { 1 };
+                            debug_state.scroll_disasm(instructions, cpu.pc, cpu.memory.len());
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::Home => {
+                            debug_state.disasm_center = None;
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::PageUp | SdlKeycode::PageDown => {
+                            let page = if key == SdlKeycode::PageUp { -MEMORY_PAGE_ROWS } else { MEMORY_PAGE_ROWS };
+                            debug_state.scroll_memory(page, cpu.memory.len());
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::F10 => {
+                            renderer.scale_mode = renderer.scale_mode.toggled();
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
+                        SdlKeycode::F11 => {
+                            let fullscreen = match canvas.window().fullscreen_state() {
+                                FullscreenType::Off => FullscreenType::Desktop,
+                                _ => FullscreenType::Off,
+                            };
+                            canvas.window_mut().set_fullscreen(fullscreen)?;
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                        },
                         SdlKeycode::LShift => { 
 
                             // toggle 'Debug' in modes hashset 
@@ -180,18 +494,16 @@ fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), Strin
                                 modes.insert(OptionalModes::Debug); 
                             }
 
-                            // get dimensions of new window based on 'Debug' status
-                            let (width, height) = match modes.contains(&OptionalModes::Debug) { 
-                                true => { (DEBUG_CANVAS_WIDTH as u32, DEBUG_CANVAS_HEIGHT as u32) },
-                                false => { (CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32) }
-                            }; 
-
-                            // set window dimensions and redraw canvas
-                            canvas
-                                .window_mut()
-                                .set_size(width, height)
-                                .expect("Failed to resize window"); 
-                            draw_entire_window(&mut canvas, &cpu, &font, modes.contains(&OptionalModes::Debug), paused_state); 
+                            // reset windowed dimensions based on 'Debug' status, fullscreen
+                            // windows keep their size and letterbox the new layout instead
+                            if canvas.window().fullscreen_state() == FullscreenType::Off {
+                                let (width, height) = logical_size(modes.contains(&OptionalModes::Debug));
+                                canvas
+                                    .window_mut()
+                                    .set_size(width, height)
+                                    .expect("Failed to resize window"); 
+                            }
+                            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state); 
                         } 
                         _ => {}, 
                     }
@@ -200,163 +512,465 @@ fn execute(mut cpu: CPU, modes: &mut HashSet<OptionalModes>) -> Result<(), Strin
             }
         }
 
-        // update cpu timers
-        if timer_refresh_time.elapsed().as_millis() >= 1_000 / timer_update_freq { 
-            timer_refresh_time = Instant::now(); 
-            
-            if paused_state { 
-                audio_device.pause(); 
-            } else {
-                cpu.update_timers();
-                if cpu.sound_timer > 0 { 
-                    audio_device.resume();
-                } else { 
-                    audio_device.pause();
-                }
+        // reload the rom when it changed on disk, a rom that fails to load leaves the old one running
+        if let Some(watcher) = rom_watcher.as_mut() {
+            match watcher.poll(Instant::now()).map(|rom| rom.and_then(|rom| cpu.reload_rom(rom))) {
+                Some(Ok(())) => {
+                    debug_state.reset_for_reload(config.keep_breakpoints);
+                    scheduler.reset_for_reload();
+                    renderer.display_filter.clear();
+                    paused_state = !config.autostart;
+                    println!("reloaded {}", watcher.path().display());
+                    draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state);
+                },
+                Some(Err(e)) => println!("ERROR:: failed to reload rom: {}", e),
+                None => {},
             }
         }
 
-        // process cpu instructions at specified cycle frequency
-        if prev_cpu_cycle_time.elapsed().as_millis() >= 1_000 / cpu_cycle_freq {
-            prev_cpu_cycle_time = Instant::now(); 
-
-            // do not update if game is paused and manual step button has not been pressed
-            if !paused_state || (paused_state && manual_step_signal) {
-                manual_step_signal = false; 
-
-                // get vector of pressed keys to be matched in cpu
-                let pressed_keys: Vec<usize> = 
-                    event_pump.keyboard_state()
-                        .pressed_scancodes()
-                        .filter_map(SdlKeycode::from_scancode)
-                        .map(|keycode| keyboard_to_chip8_input_map.get(&keycode))
-                        .filter(|keycode| *keycode != None)
-                        .map(|keycode| keycode.unwrap())
-                        .map(|key| get_chip8_key_idx(key))
-                        .collect(); 
-
-                cpu.step(pressed_keys);
+        // a manual step runs a single instruction, with a vblank so a waiting DXYN can finish
+        if paused_state && manual_step_signal {
+            manual_step_signal = false; 
+            let was_halted = cpu.is_halted(); 
+            cpu.vblank(); 
+            cpu.step(); 
+            if let (false, Some(reason)) = (was_halted, cpu.halt_reason.as_ref()) { println!("halted at {}", reason); }
+        }
+
+        // run the 60 Hz frames that are due: timers, instructions and one redraw
+        let frames_due = scheduler.frames_due(Instant::now()); 
+        for _ in 0..frames_due {
+            if paused_state { 
+                audio_device.lock().set_gate(false);
+                continue; 
+            }
+
+            let was_halted = cpu.is_halted(); 
+            let outcome = scheduler.run_frame(&mut cpu, &mut debug_state); 
+            if let Some(recorder) = wav_recorder.as_mut() {
+                recorder.tick(cpu.sound_timer);
+            }
+            audio_device.lock().set_gate(cpu.sound_timer > 0);
+
+            // stop before running an instruction that has a breakpoint
+            if outcome == FrameOutcome::HitBreakpoint {
+                paused_state = true;
+                println!("hit breakpoint at x{:03x}", cpu.pc);
+            }
+
+            // a halted CPU pauses so the debugger shows where it stopped, ESC resets it
+            if let Some(reason) = cpu.halt_reason.as_ref() {
+                paused_state = true;
+                if !was_halted { println!("halted at {}", reason); }
             }
         }
 
-        // draw pixels on canvas at specified screen refresh frequency
-        if prev_screen_refresh_time.elapsed().as_millis() >= 1_000 / screen_refresh_freq { 
-            prev_screen_refresh_time = Instant::now(); 
-            draw_entire_window(&mut canvas, &cpu, &font, modes.contains(&OptionalModes::Debug), paused_state); 
+        // the display filter steps once per scheduled redraw, event redraws reuse its intensities
+        if frames_due > 0 { 
+            debug_state.track_memory_changes(&cpu.memory);
+            renderer.display_filter.apply(&cpu.pixels);
+            draw_entire_window(&mut canvas, &mut renderer, &cpu, &debug_state, modes.contains(&OptionalModes::Debug), paused_state); 
         }
     }
 
+    if modes.contains(&OptionalModes::Debug) {
+        println!("call stack high-water mark: {} of {}", cpu.stack_high_water(), cpu.stack.len());
+    }
+
+    // write recorded audio on exit
+    if let (Some(recorder), Some(wav_path)) = (wav_recorder, config.wav_path.as_ref()) {
+        recorder.write_wav(Path::new(wav_path))
+            .map_err(|e| format!("ERROR:: failed to write wav file {}: {}", wav_path, e))?;
+    }
+
     Ok(())
 }
 
-fn get_file_from_file_chooser_dialog() -> String { 
-    let output = process::Command::new("sh")
-        .arg("-c")
-        .arg(format!("osascript {}/file-dialog.scpt", OSA_SCRIPTS_PATH))
-        .output()
-        .expect("ERROR: failed to get output from file chooser dialog command"); 
+// list directories and roms in the window until one loads, None when the user quits
+fn browse_for_rom(config: &Config, canvas: &mut Canvas<Window>, renderer: &mut Renderer, event_pump: &mut EventPump) -> Result<Option<(CPU, String)>, String> {
+    let debug = config.modes.contains(&OptionalModes::Debug);
+    let mut history = history_path().map(|path| RomHistory::load(&path)).unwrap_or_default();
+    let mut browser = RomBrowser::from_history(&history);
+
+    loop {
+        // the next launch opens in the directory the browser was left in
+        if history.visit_dir(&browser.dir) {
+            if let Some(path) = history_path() {
+                history.save(&path).unwrap_or_else(|e| println!("failed to save rom history: {}", e));
+            }
+        }
 
-    let output_str = String::from_utf8_lossy(&output.stdout)
-        .trim()
-        .to_string(); 
+        let rows = browser_rows(renderer.line_height(), debug);
+        browser.keep_visible(rows);
+        draw_rom_browser(&browser, canvas, renderer, debug);
+
+        let mut chosen: Option<PathBuf> = None;
+        match event_pump.wait_event() {
+            Event::Quit { .. } => return Ok(None),
+            Event::KeyDown { keycode: Some(key), .. } => match key {
+                SdlKeycode::Escape => return Ok(None),
+                SdlKeycode::Up => browser.move_selection(-1),
+                SdlKeycode::Down => browser.move_selection(1),
+                SdlKeycode::PageUp => browser.move_selection(-(rows as isize)),
+                SdlKeycode::PageDown => browser.move_selection(rows as isize),
+                SdlKeycode::Home => browser.move_selection(-(browser.entries.len() as isize)),
+                SdlKeycode::End => browser.move_selection(browser.entries.len() as isize),
+                SdlKeycode::Backspace => browser.go_up(),
+                SdlKeycode::Return => chosen = browser.activate(),
+                _ => {},
+            },
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (logical_x, logical_y) = renderer.viewport(canvas, debug).to_logical(x, y);
+                if let Some(idx) = browser_entry_at(logical_x, logical_y, renderer.line_height(), debug, &browser) {
+                    browser.selected = idx;
+                    chosen = browser.activate();
+                }
+            },
+            Event::MouseWheel { y, .. } => browser.move_selection(-3 * y as isize),
+            _ => {},
+        }
 
-    let err_str = String::from_utf8_lossy(&output.stderr)
-        .trim()
-        .to_string(); 
+        // a rom that fails to load keeps the browser open with the error shown
+        if let Some(path) = chosen {
+            let filename = path.display().to_string();
+            match load_cpu(config, &filename) {
+                Ok(cpu) => {
+                    remember_rom(&filename);
+                    return Ok(Some((cpu, filename)));
+                },
+                Err(e) => browser.error = Some(e),
+            }
+        }
+    }
+}
 
-    if err_str.len() != 0 { 
-        println!("file not loaded from file chooser dialog ... exiting now"); 
-        std::process::exit(0); 
+// read the rom and set up a cpu for it, the extension picks the platform unless one was given
+fn load_cpu(config: &Config, filename: &str) -> Result<CPU, String> {
+    let path = Path::new(filename);
+    if !is_rom_path(path) { 
+        return Err(format!("{}: not a rom extension (.ch8, .c8, .sc8, .xo8, .bin, .hex, .txt or .gif)", filename)); 
+    }
+    let rom = read_rom_image(path)?;
+    let platform = config.platform.or(rom.platform);
+
+    // explicit sizes win over the platform's, which win over the VIP's 4 KiB and 12 return addresses
+    let memory_size = config.memory_size
+        .or(platform.map(|platform| platform.memory_size()))
+        .unwrap_or(DEFAULT_MEMORY_SIZE);
+    let stack_depth = config.stack_depth
+        .or(platform.map(|platform| platform.stack_depth()))
+        .unwrap_or(DEFAULT_STACK_DEPTH);
+    let mut cpu: CPU = CPU::with_limits(memory_size, stack_depth);  
+    cpu.quirks = config.quirks.or(rom.quirks).or(platform.map(|platform| platform.quirks())).unwrap_or_default();
+    cpu.machine_code = config.machine_code;
+
+    // an explicit font or base wins over the platform's, which wins over the built-in font at x000
+    if platform.is_some() || config.font.is_some() || config.font_base.is_some() {
+        let font = config.font
+            .or(platform.map(|platform| *platform.font_set().glyphs()))
+            .unwrap_or(*cpu.font());
+        let font_base = config.font_base
+            .or(platform.map(|platform| platform.font_base()))
+            .unwrap_or(cpu.font_range().start);
+        cpu.load_font(&font, font_base)?;
+    }
+    for (addr, routine) in config.routines.iter() {
+        cpu.register_routine(*addr, *routine);
     }
+    cpu.load_rom(rom.bytes).map_err(|e| format!("{}: {}", filename, e))?;
+    Ok(cpu)
+}
 
-    output_str
+// add the rom to the recent list shown by the rom browser
+fn remember_rom(filename: &str) {
+    if let Some(path) = history_path() {
+        let mut history = RomHistory::load(&path);
+        history.add_recent(Path::new(filename));
+        history.save(&path).unwrap_or_else(|e| println!("failed to save rom history: {}", e));
+    }
 }
 
-fn parse_command_line_args() -> (Option<String>, HashSet<OptionalModes>) { 
+fn parse_command_line_args() -> Config { 
 
     let argv: Vec<String> = env::args().collect(); 
-    let mut filename: Option<String> = None; 
-    let mut optional_modes: HashSet<OptionalModes> = HashSet::new(); 
-    let ch8_re_pattern = Regex::new("\\.ch8$").expect("ERROR: regex was not created successfully");
+    let mut config = Config {
+        filename: None,
+        modes: HashSet::new(),
+        wav_path: None,
+        beeper: BeeperSettings::default(),
+        scale_mode: ScaleMode::Integer,
+        fullscreen: false,
+        palette: Palette::from_name("classic").expect("ERROR: missing classic palette"),
+        persistence: Persistence::Off,
+        crt: CrtSettings::default(),
+        symbols: SymbolTable::default(),
+        keypad: false,
+        quirks: None,
+        budget: FrameBudget::Instructions(DEFAULT_INSTRUCTIONS_PER_FRAME),
+        machine_code: MachineCodeMode::default(),
+        routines: Vec::new(),
+        platform: None,
+        font: None,
+        font_base: None,
+        stack_depth: None,
+        memory_size: None,
+        watch: false,
+        keep_breakpoints: false,
+        autostart: false,
+    };
 
-    // loop through args and mark flags / parse filename
-    for (idx, value) in argv.iter().enumerate() { 
-        if idx == 0 { continue; }
+    // loop through args and mark flags / parse filename and flag values
+    let mut args = argv.iter().skip(1);
+    while let Some(value) = args.next() {
         match value.as_str() { 
-            "-d" | "--debug" => optional_modes.insert(OptionalModes::Debug), 
+            "-d" | "--debug" => { config.modes.insert(OptionalModes::Debug); },
             "-h" | "--help" => { 
                 print_usage(); 
                 std::process::exit(0); 
             }, 
-            _ if ch8_re_pattern.is_match(value.as_str()) => {
-                filename = Some(value.to_owned()); 
-                true
+            "-w" | "--wav" => {
+                let wav_path = args.next().expect("ERROR: expected a wav file path after --wav");
+                config.wav_path = Some(wav_path.to_owned());
+            },
+            "--waveform" => {
+                config.beeper.waveform = args.next()
+                    .and_then(|name| Waveform::from_name(name))
+                    .expect("ERROR: expected one of square, triangle, sine or noise after --waveform");
+            },
+            "--pitch" => {
+                config.beeper.frequency = args.next()
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .filter(|frequency| *frequency > 0.0)
+                    .expect("ERROR: expected a frequency in Hz after --pitch");
+            },
+            "--volume" => {
+                config.beeper.volume = args.next()
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .filter(|volume| (0.0..=1.0).contains(volume))
+                    .expect("ERROR: expected a volume between 0.0 and 1.0 after --volume");
+            },
+            "--attack" => {
+                config.beeper.attack_ms = args.next()
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .filter(|attack_ms| *attack_ms >= 0.0)
+                    .expect("ERROR: expected a time in ms after --attack");
+            },
+            "--release" => {
+                config.beeper.release_ms = args.next()
+                    .and_then(|value| value.parse::<f32>().ok())
+                    .filter(|release_ms| *release_ms >= 0.0)
+                    .expect("ERROR: expected a time in ms after --release");
+            },
+            "--scale" => {
+                config.scale_mode = args.next()
+                    .and_then(|name| ScaleMode::from_name(name))
+                    .expect("ERROR: expected one of integer or fit after --scale");
+            },
+            "-f" | "--fullscreen" => { config.fullscreen = true; },
+            "-k" | "--keypad" => { config.keypad = true; },
+            "--quirks" => {
+                let list = args.next().expect("ERROR: expected a comma separated list of quirks after --quirks");
+                config.quirks = Some(Quirks::parse(list).unwrap_or_else(|e| panic!("ERROR: {}", e)));
+            },
+            "--machine-code" => {
+                config.machine_code = args.next()
+                    .and_then(|name| MachineCodeMode::from_name(name))
+                    .expect("ERROR: expected one of ignore, halt or dispatch after --machine-code");
+            },
+            "--routine" => {
+                let routine = args.next().expect("ERROR: expected addr=name after --routine");
+                config.routines.push(parse_routine(routine).unwrap_or_else(|e| panic!("ERROR: {}", e)));
+                config.machine_code = MachineCodeMode::Dispatch;
+            },
+            "--platform" => {
+                config.platform = args.next()
+                    .and_then(|name| Platform::from_name(name))
+                    .map(Some)
+                    .expect("ERROR: expected one of vip, dream6800, eti660, chip48, schip or xochip after --platform");
+            },
+            "--font" => {
+                let name = args.next().expect("ERROR: expected a font set name or font file after --font");
+                config.font = match FontSet::from_name(name) {
+                    Some(font_set) => Some(*font_set.glyphs()),
+                    None => {
+                        let bytes = fs::read(name).unwrap_or_else(|e| panic!("ERROR: failed to read font file {}: {}", name, e));
+                        Some(parse_font(&bytes).unwrap_or_else(|e| panic!("ERROR: {}: {}", name, e)))
+                    },
+                };
+            },
+            "--font-base" => {
+                config.font_base = args.next()
+                    .and_then(|value| usize::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+                    .filter(|base| base.checked_add(FONT_BYTES).is_some_and(|end| end <= ROM_START_ADDR))
+                    .map(Some)
+                    .expect("ERROR: expected a hex address such as 0x050 after --font-base, low enough for the font to end by x200");
+            },
+            "--stack-depth" => {
+                config.stack_depth = args.next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .filter(|depth| *depth > 0)
+                    .map(Some)
+                    .expect("ERROR: expected a positive number of return addresses after --stack-depth");
+            },
+            "--memory" => {
+                config.memory_size = match args.next().map(|value| value.as_str()) {
+                    Some("4k") => Some(0x1000),
+                    Some("64k") => Some(0x10000),
+                    _ => panic!("ERROR: expected 4k or 64k after --memory"),
+                };
+            },
+            "--watch" => { config.watch = true; },
+            "--keep-breakpoints" => { config.keep_breakpoints = true; },
+            "--autostart" => { config.autostart = true; },
+            "--ipf" => {
+                config.budget = args.next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .filter(|ipf| *ipf > 0)
+                    .map(FrameBudget::Instructions)
+                    .expect("ERROR: expected a positive number of instructions per frame after --ipf");
+            },
+            "--vip-timing" => {
+                config.budget = FrameBudget::VipCycles(VIP_CYCLES_PER_FRAME);
+            },
+            "--cpf" => {
+                config.budget = args.next()
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .filter(|cpf| *cpf > 0)
+                    .map(FrameBudget::VipCycles)
+                    .expect("ERROR: expected a positive number of VIP machine cycles per frame after --cpf");
+            },
+            "-p" | "--palette" => {
+                config.palette = args.next()
+                    .and_then(|name| Palette::from_name(name))
+                    .expect("ERROR: expected classic, green, amber, lcd, high-contrast or #RRGGBB,#RRGGBB after --palette");
+            },
+            "--persistence" => {
+                config.persistence = args.next()
+                    .and_then(|name| Persistence::from_name(name))
+                    .expect("ERROR: expected off, or, decay or decay:{strength} after --persistence");
+            },
+            "--crt" => {
+                config.crt = args.next()
+                    .and_then(|value| CrtSettings::parse(value))
+                    .expect("ERROR: expected on, off or scanlines=,grid=,bloom=,curvature= strengths after --crt");
+            },
+            "--symbols" => {
+                let symbols_path = args.next().expect("ERROR: expected a symbol file path after --symbols");
+                config.symbols = SymbolTable::load(Path::new(symbols_path))
+                    .unwrap_or_else(|e| panic!("ERROR: failed to load symbol file: {}", e));
+            },
+            _ if is_rom_path(Path::new(value)) => {
+                config.filename = Some(value.to_owned());
             }, 
             _ => panic!("ERROR: encountered unknown value: {}", value.as_str())
         }; 
     }
 
-    // return filename and parsed modes
-    (filename, optional_modes)
+    config
 }
 
 fn print_usage() { 
     print!(
-"  USAGE:: ./chip8 [-d | -s | {{filename}}.ch8]
+"  USAGE:: ./chip8 [-d | -s | -w {{file}}.wav | {{filename}}.{{ch8 | c8 | sc8 | xo8 | bin | hex | txt | gif}}]
 
    DESCRIPTION: This is a Chip-8 interpreter built in rust 
 
+   ROMS: binary .ch8, .c8, .sc8, .xo8 and .bin files, or .hex and .txt hex dumps with pairs of hex
+         digits per byte. .sc8 selects the schip platform and .xo8 the xochip platform unless
+         --platform is given. Octo cartridge .gif files are assembled from the Octo source they
+         carry and pick the platform and quirks from the cartridge's options.
+         Without a rom argument the window opens a rom browser: arrows or the mouse select,
+         Enter opens a rom or directory, Backspace goes up and Esc quits. Recently opened roms
+         and the last directory are remembered in chip8/history under the user config directory.
+
    OPTIONS: 
      -d | --debug -> turns on debugging information about current instructions and memory
      -h | --help  -> print usage and return
+     -w | --wav   -> record emulated audio and write it to the given wav file on exit
+     --waveform {{square | triangle | sine | noise}} -> beeper waveform (default square)
+     --pitch {{hz}}   -> beeper frequency (default 261)
+     --volume {{0-1}} -> beeper volume (default 0.1)
+     --attack {{ms}}  -> time for the beeper to fade in (default 4)
+     --release {{ms}} -> time for the beeper to fade out (default 8)
+     --scale {{integer | fit}} -> scale the display by whole pixels or fill the window (default integer)
+     -f | --fullscreen -> start in fullscreen
+     -k | --keypad -> show the on-screen keypad, keys can be clicked with the mouse
+     --quirks {{fx0a-immediate,sprite-wrap,collision-rows,display-wait,shift-in-place}} -> comma separated interpreter
+                       quirks to enable (default the platform's, none for the VIP)
+                       fx0a-immediate: FX0A takes a held key at once instead of waiting for its release
+                       sprite-wrap: sprites wrap around the screen edges instead of being clipped
+                       collision-rows: DXYN sets VF to the number of rows that collided
+                       display-wait: DXYN waits for the next 60 Hz frame, one sprite per frame as on the VIP
+                       shift-in-place: 8XY6/8XYE shift Vx instead of Vy, on by default for chip48 and schip
+     --ipf {{n}} -> instructions run per 60 Hz frame (default 1)
+     --watch -> reload the rom whenever the file changes on disk, paused and without breakpoints
+     --keep-breakpoints -> keep the debugger breakpoints when --watch reloads the rom
+     --autostart -> start running right after --watch reloads the rom
+     --platform {{vip | dream6800 | eti660 | chip48 | schip | xochip}} -> machine the ROM was written for,
+                       picks its font, where the font is placed (x000, or x050 for schip), the stack depth
+                       (12, 16 for chip48 and schip, up to 256 for xochip) and memory (4k, 64k for xochip)
+     --stack-depth {{n}} -> return addresses the call stack holds (default 12)
+     --memory {{4k | 64k}} -> memory size (default 4k), with 64k I can be loaded past 4k with F000 NNNN
+     --font {{vip | dream6800 | eti660 | schip | file}} -> hex digit font, a file holds 80 bytes of glyphs
+                       (default schip)
+     --font-base {{addr}} -> hex address to place the font at, below x200 (default x000)
+     --machine-code {{ignore | halt | dispatch}} -> what a 0NNN call to an RCA 1802 machine code routine
+                       does: skip it, stop the CPU with an error or run the routine given by --routine (default halt)
+     --routine {{addr}}={{nop | cls}} -> stand in for the ROM's machine code routine at addr with a builtin,
+                       can be repeated and turns on --machine-code dispatch
+     --vip-timing -> charge each instruction its approximate COSMAC VIP cycle cost and run a VIP frame
+                       worth of cycles per 60 Hz frame instead of a fixed instruction count
+     --cpf {{n}} -> like --vip-timing with n machine cycles per frame (VIP is {})
+     -p | --palette {{name | #RRGGBB,#RRGGBB}} -> display colors: classic, green, amber, lcd, high-contrast
+                       or a custom foreground,background pair (default classic)
+     --persistence {{off | or | decay[:strength]}} -> reduce flicker by keeping pixels lit for the last
+                       two frames or fading them out, strength 0.0 - 1.0 (default off)
+     --crt {{on | off | scanlines=0.35,grid=0.25,bloom=0.3,curvature=0.08}} -> retro CRT look, each
+                       strength 0.0 - 1.0 and omitted ones keep the defaults shown (default off)
+     --symbols {{file}} -> label addresses in the debugger, one label = 0x200 or 0x200 label per line
 
    KEY COMMANDS (while program is running): 
      ESCAPE  : reset ROM and set CPU to PAUSED
      L_SHIFT : toggle debugging interface in window
+     M       : mute / unmute audio
+     K       : toggle the on-screen keypad, lit keys are held and outlined keys were last tested by the ROM
+     TAB     : cycle the bottom right debug panel between I REGISTER, SPRITES, MEMORY and CALL STACK
+     G       : jump the memory or sprite panel to a typed hex address (RETURN to jump, ESCAPE to cancel)
+     I / L / - / = : sprite panel: follow I again, toggle 8xN and 16x16, change the 8xN height
+     PAGE UP / PAGE DOWN / MOUSE WHEEL : scroll the memory panel
+     UP / DOWN / MOUSE WHEEL : scroll the disassembly, HOME : make it follow the PC again
+     B       : toggle a breakpoint at the PC, clicking a disassembly row toggles one on that row
+     LEFT CLICK : while PAUSED, edit the clicked register, timer or memory byte by typing a hex
+                  value (RETURN to apply, ESCAPE to cancel)
+     CTRL + Z   : undo the last register or memory edit
+     F9      : toggle CRT filter
+     F10     : toggle scaling between INTEGER and FIT
+     F11     : toggle fullscreen
+     F12     : save a PNG screenshot of the display to the current directory
+     P       : cycle color palettes
+     O       : cycle flicker reduction between OFF, OR and DECAY
      RETURN  : manually step through CPU
      SPACE   : toggle CPU state between PAUSED and RUNNING
-"
+",
+        VIP_CYCLES_PER_FRAME
     ); 
 }
 
 pub fn main() {
 
-    // parse input file name
-    let (filename_option, mut modes)= parse_command_line_args(); 
-
-    // get file from dialog if filename was not parse in arguments
-    let filename: String = match filename_option { 
-        Some(value) => value, 
-        None => get_file_from_file_chooser_dialog()
-    };
-
-    // match file to correct extension
-    if !Regex::new("\\.ch8$").unwrap().is_match(&filename) { 
-        println!("ERROR:: file does not have correct extension"); 
-        std::process::exit(1);
-    }
-
-    // open file 
-    let file: File = match fs::File::open(&filename) { 
-        Ok(f) => f, 
-        Err(_) => panic!("Failed to open file: {filename}")
-    };
-
-    // read bytes into byte vector
-    let mut rom_bytes = Vec::new(); 
-    for byte in file.bytes() { 
-        rom_bytes.push(byte.unwrap()); 
-    }
-
-    // instantiate cpu and load rom bytes into memory
-    let mut cpu: CPU = CPU::new();  
-    cpu.load_rom(rom_bytes);
+    // parse input file name and options
+    let mut config = parse_command_line_args();
 
-    if modes.contains(&OptionalModes::Debug) { 
-        println!("{}", cpu.dump_memory()); 
-        println!("Starting CPU with the following modes: {:?}", modes); 
-    }  
+    // load the rom given in the arguments, without one the window opens a rom browser
+    let cpu: Option<CPU> = config.filename.clone().map(|filename| {
+        let cpu = load_cpu(&config, &filename).unwrap_or_else(|e| {
+            println!("ERROR:: {}", e); 
+            std::process::exit(1);
+        });
+        remember_rom(&filename);
+        cpu
+    });
 
     // run cpu
-    execute(cpu, &mut modes).unwrap();
-}
\ No newline at end of file
+    execute(cpu, &mut config).unwrap();
+}
@@ -0,0 +1,971 @@
+pub mod octo {
+
+    use std::collections::{HashMap, VecDeque};
+    use std::f64::consts;
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    const ROM_START: usize = 0x200;
+    const ADDRESS_SPACE: usize = 0x10000;
+
+    const UNARY_OPERATORS: [&str; 13] = ["-", "~", "!", "abs", "sqrt", "sin", "cos", "tan", "exp", "log", "sign", "ceil", "floor"];
+    const BINARY_OPERATORS: [&str; 19] = [
+        "+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>", "pow", "min", "max", "<", "<=", ">", ">=", "==", "!=",
+    ];
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    #[derive(Clone, Debug)]
+    struct Token {
+        text: String,
+        line: usize,
+        quoted: bool, // a "string", never a keyword or name
+    }
+
+    // an operand that is either known or a label defined further down
+    #[derive(Clone, Debug)]
+    enum Operand {
+        Value(f64),
+        Label(String),
+    }
+
+    // how an address is written into the bytes already emitted for it
+    #[derive(Clone, Copy, Debug)]
+    enum Patch {
+        Address12,      // low 12 bits of an NNNN instruction
+        Address16,      // two bytes, high byte first
+        UnpackHigh(u8), // a nibble and the top 4 bits of a 12-bit address
+        LongHigh,       // the high byte of a 16-bit address
+        Low,            // the low byte of an address
+    }
+
+    // open control flow, each holding the jumps to patch once its end is known
+    enum Block {
+        Begin(usize),
+        Loop { start: usize, exits: Vec<usize> },
+    }
+
+    struct Macro {
+        params: Vec<String>,
+        body: Vec<Token>,
+    }
+
+    struct Assembler {
+        tokens: VecDeque<Token>,
+        memory: Vec<u8>,
+        here: usize,
+        end: usize,
+        labels: HashMap<String, usize>,
+        constants: HashMap<String, f64>,
+        aliases: HashMap<String, u8>,
+        macros: HashMap<String, Macro>,
+        string_modes: HashMap<String, HashMap<char, (usize, Vec<Token>)>>,
+        patches: Vec<(usize, String, Patch, usize)>, // address, label, how and source line
+        blocks: Vec<Block>,
+        line: usize,
+    }
+
+    impl Assembler {
+
+        fn run(&mut self) -> Result<Vec<u8>, String> {
+            // like Octo, the program starts with a jump to main
+            self.emit(0x10, 0x00)?;
+            self.patches.push((ROM_START, "main".to_string(), Patch::Address12, 0));
+
+            while !self.tokens.is_empty() {
+                self.statement()?;
+            }
+            match self.blocks.last() {
+                Some(Block::Begin(_)) => return Err("begin without end".to_string()),
+                Some(Block::Loop { .. }) => return Err("loop without again".to_string()),
+                None => {},
+            }
+            if !self.labels.contains_key("main") {
+                return Err("program has no main label".to_string());
+            }
+            for (addr, name, patch, line) in std::mem::take(&mut self.patches) {
+                self.line = line;
+                let value = *self.labels.get(&name).ok_or(format!("undefined name {}", name))?;
+                self.apply(addr, patch, value as f64)?;
+            }
+            Ok(self.memory[ROM_START..self.end].to_vec())
+        }
+
+        fn statement(&mut self) -> Result<(), String> {
+            let token = self.next()?;
+            if token.quoted {
+                return Err(format!("unexpected string {:?}", token.text));
+            }
+            if let Some(x) = self.register_of(&token.text) {
+                return self.register_statement(x);
+            }
+            match token.text.as_str() {
+                ":" => {
+                    let name = self.name()?;
+                    self.define_label(name, self.here)?;
+                },
+                ":next" => {
+                    let name = self.name()?;
+                    self.define_label(name, self.here + 1)?;
+                },
+                ":alias" => {
+                    let name = self.name()?;
+                    let register = match self.peek() {
+                        Some("{") => match self.calc()? {
+                            value if (0.0..16.0).contains(&value) => value as u8,
+                            value => return Err(format!("v{} is not a register", value)),
+                        },
+                        _ => self.register()?,
+                    };
+                    self.aliases.insert(name, register);
+                },
+                ":const" => {
+                    let name = self.name()?;
+                    let value = self.value()?;
+                    if self.constants.contains_key(&name) || self.labels.contains_key(&name) {
+                        return Err(format!("{} is already defined", name));
+                    }
+                    self.constants.insert(name, value);
+                },
+                ":calc" => {
+                    let name = self.name()?;
+                    let value = self.calc()?;
+                    self.constants.insert(name, value);
+                },
+                ":byte" => {
+                    let byte = self.byte()?;
+                    self.emit_byte(byte)?;
+                },
+                ":pointer" => {
+                    let target = self.operand()?;
+                    let at = self.here;
+                    self.emit(0x00, 0x00)?;
+                    self.resolve(at, Patch::Address16, target)?;
+                },
+                ":org" => {
+                    let addr = self.value()?;
+                    if !(ROM_START as f64..ADDRESS_SPACE as f64).contains(&addr) {
+                        return Err(format!("can not :org to {} outside x200 to xffff", addr));
+                    }
+                    self.here = addr as usize;
+                },
+                ":unpack" => {
+                    // v0 and v1 get the address, with a nibble above it unless it is a long one
+                    let high = match self.peek() {
+                        Some("long") => {
+                            self.next()?;
+                            Patch::LongHigh
+                        },
+                        _ => Patch::UnpackHigh(self.nibble()?),
+                    };
+                    let target = self.operand()?;
+                    let at = self.here;
+                    self.emit(0x60, 0x00)?;
+                    self.emit(0x61, 0x00)?;
+                    self.resolve(at + 1, high, target.clone())?;
+                    self.resolve(at + 3, Patch::Low, target)?;
+                },
+                ":call" => self.address_instruction(0x20)?,
+                ":macro" => {
+                    let name = self.name()?;
+                    let mut params: Vec<String> = Vec::new();
+                    while self.peek() != Some("{") {
+                        params.push(self.name()?);
+                    }
+                    let body = self.braced()?;
+                    self.macros.insert(name, Macro { params, body });
+                },
+                ":stringmode" => {
+                    let name = self.name()?;
+                    let alphabet = self.string()?;
+                    let body = self.braced()?;
+                    let mode = self.string_modes.entry(name).or_default();
+                    for (value, c) in alphabet.chars().enumerate() {
+                        mode.insert(c, (value, body.clone()));
+                    }
+                },
+                ":assert" => {
+                    let message = match self.tokens.front() {
+                        Some(token) if token.quoted => Some(self.string()?),
+                        _ => None,
+                    };
+                    if self.calc()? == 0.0 {
+                        return Err(format!("assertion failed{}", message.map(|message| format!(": {}", message)).unwrap_or_default()));
+                    }
+                },
+                // debugger hints have no effect on the program
+                ":breakpoint" | ":proto" => { self.next()?; },
+                ":monitor" => {
+                    self.next()?;
+                    self.next()?;
+                },
+                ";" | "return" => self.emit(0x00, 0xEE)?,
+                "clear" => self.emit(0x00, 0xE0)?,
+                "exit" => self.emit(0x00, 0xFD)?,
+                "lores" => self.emit(0x00, 0xFE)?,
+                "hires" => self.emit(0x00, 0xFF)?,
+                "scroll-right" => self.emit(0x00, 0xFB)?,
+                "scroll-left" => self.emit(0x00, 0xFC)?,
+                "scroll-down" => {
+                    let n = self.nibble()?;
+                    self.emit(0x00, 0xC0 | n)?;
+                },
+                "scroll-up" => {
+                    let n = self.nibble()?;
+                    self.emit(0x00, 0xD0 | n)?;
+                },
+                "audio" => self.emit(0xF0, 0x02)?,
+                "plane" => {
+                    let n = self.nibble()?;
+                    self.emit(0xF0 | n, 0x01)?;
+                },
+                "jump" => self.address_instruction(0x10)?,
+                "jump0" => self.address_instruction(0xB0)?,
+                "native" => self.address_instruction(0x00)?,
+                "sprite" => {
+                    let x = self.register()?;
+                    let y = self.register()?;
+                    let n = self.nibble()?;
+                    self.emit(0xD0 | x, (y << 4) | n)?;
+                },
+                "bcd" => self.register_instruction(0x33)?,
+                "saveflags" => self.register_instruction(0x75)?,
+                "loadflags" => self.register_instruction(0x85)?,
+                "save" | "load" => {
+                    let x = self.register()?;
+                    let save = token.text == "save";
+                    if self.peek() == Some("-") {
+                        self.next()?;
+                        let y = self.register()?;
+                        self.emit(0x50 | x, (y << 4) | if save { 0x2 } else { 0x3 })?;
+                    } else {
+                        self.emit(0xF0 | x, if save { 0x55 } else { 0x65 })?;
+                    }
+                },
+                "delay" | "buzzer" | "pitch" => {
+                    self.expect(":=")?;
+                    let low = match token.text.as_str() {
+                        "delay" => 0x15,
+                        "buzzer" => 0x18,
+                        _ => 0x3A,
+                    };
+                    self.register_instruction(low)?;
+                },
+                "i" => self.i_statement()?,
+                "if" => {
+                    // then guards one instruction and begin a block, so then skips it when the condition fails
+                    let block = self.tokens.iter()
+                        .find(|token| !token.quoted && (token.text == "then" || token.text == "begin"))
+                        .map(|token| token.text.clone())
+                        .ok_or("if without then or begin")?;
+                    self.condition(block == "then")?;
+                    self.expect(&block)?;
+                    if block == "begin" {
+                        let at = self.here;
+                        self.emit(0x10, 0x00)?;
+                        self.blocks.push(Block::Begin(at));
+                    }
+                },
+                "else" => match self.blocks.pop() {
+                    Some(Block::Begin(at)) => {
+                        let jump = self.here;
+                        self.emit(0x10, 0x00)?;
+                        self.apply(at, Patch::Address12, self.here as f64)?;
+                        self.blocks.push(Block::Begin(jump));
+                    },
+                    _ => return Err("else without begin".to_string()),
+                },
+                "end" => match self.blocks.pop() {
+                    Some(Block::Begin(at)) => self.apply(at, Patch::Address12, self.here as f64)?,
+                    _ => return Err("end without begin".to_string()),
+                },
+                "loop" => self.blocks.push(Block::Loop { start: self.here, exits: Vec::new() }),
+                "while" => {
+                    self.condition(false)?;
+                    let at = self.here;
+                    self.emit(0x10, 0x00)?;
+                    match self.blocks.iter_mut().rev().find_map(|block| match block {
+                        Block::Loop { exits, .. } => Some(exits),
+                        _ => None,
+                    }) {
+                        Some(exits) => exits.push(at),
+                        None => return Err("while outside a loop".to_string()),
+                    }
+                },
+                "again" => match self.blocks.pop() {
+                    Some(Block::Loop { start, exits }) => {
+                        let at = self.here;
+                        self.emit(0x10, 0x00)?;
+                        self.apply(at, Patch::Address12, start as f64)?;
+                        for exit in exits {
+                            self.apply(exit, Patch::Address12, self.here as f64)?;
+                        }
+                    },
+                    _ => return Err("again without loop".to_string()),
+                },
+                name if self.macros.contains_key(name) => self.expand_macro(name)?,
+                name if self.string_modes.contains_key(name) => self.expand_string_mode(name)?,
+                // a bare number is a byte and a bare label a call
+                text if parse_number(text).is_some() || self.constants.contains_key(text) => {
+                    let byte = to_byte(self.lookup(text).unwrap_or_default())?;
+                    self.emit_byte(byte)?;
+                },
+                _ => {
+                    self.tokens.push_front(token);
+                    self.address_instruction(0x20)?;
+                },
+            }
+            Ok(())
+        }
+
+        fn register_statement(&mut self, x: u8) -> Result<(), String> {
+            let op = self.next()?.text;
+            if op == ":=" {
+                match self.peek() {
+                    Some("random") => {
+                        self.next()?;
+                        let mask = self.byte()?;
+                        return self.emit(0xC0 | x, mask);
+                    },
+                    Some("key") => {
+                        self.next()?;
+                        return self.emit(0xF0 | x, 0x0A);
+                    },
+                    Some("delay") => {
+                        self.next()?;
+                        return self.emit(0xF0 | x, 0x07);
+                    },
+                    _ => {},
+                }
+            }
+
+            let source = self.next()?;
+            if let Some(y) = self.register_of(&source.text) {
+                let n = match op.as_str() {
+                    ":=" => 0x0,
+                    "|=" => 0x1,
+                    "&=" => 0x2,
+                    "^=" => 0x3,
+                    "+=" => 0x4,
+                    "-=" => 0x5,
+                    ">>=" => 0x6,
+                    "=-" => 0x7,
+                    "<<=" => 0xE,
+                    _ => return Err(format!("unknown register operator {}", op)),
+                };
+                return self.emit(0x80 | x, (y << 4) | n);
+            }
+            self.tokens.push_front(source);
+            let n = self.byte()?;
+            match op.as_str() {
+                ":=" => self.emit(0x60 | x, n),
+                "+=" => self.emit(0x70 | x, n),
+                "-=" => self.emit(0x70 | x, n.wrapping_neg()),
+                _ => Err(format!("{} needs a register on the right", op)),
+            }
+        }
+
+        fn i_statement(&mut self) -> Result<(), String> {
+            match self.next()?.text.as_str() {
+                ":=" => match self.peek() {
+                    Some("hex") => {
+                        self.next()?;
+                        self.register_instruction(0x29)
+                    },
+                    Some("bighex") => {
+                        self.next()?;
+                        self.register_instruction(0x30)
+                    },
+                    Some("long") => {
+                        self.next()?;
+                        let target = self.operand()?;
+                        let at = self.here;
+                        self.emit(0xF0, 0x00)?;
+                        self.emit(0x00, 0x00)?;
+                        self.resolve(at + 2, Patch::Address16, target)
+                    },
+                    _ => self.address_instruction(0xA0),
+                },
+                "+=" => self.register_instruction(0x1E),
+                op => Err(format!("unknown operator i {}", op)),
+            }
+        }
+
+        // emit the test that skips the next instruction when the condition holds, or when it
+        // fails with negate. <, >, <= and >= compare through vf like Octo does
+        fn condition(&mut self, negate: bool) -> Result<(), String> {
+            let x = self.register()?;
+            let op = self.next()?.text;
+            let op = match (negate, op.as_str()) {
+                (false, op) => op,
+                (true, "==") => "!=",
+                (true, "!=") => "==",
+                (true, "key") => "-key",
+                (true, "-key") => "key",
+                (true, "<") => ">=",
+                (true, ">=") => "<",
+                (true, ">") => "<=",
+                (true, "<=") => ">",
+                (true, op) => op,
+            };
+            match op {
+                "key" => self.emit(0xE0 | x, 0x9E),
+                "-key" => self.emit(0xE0 | x, 0xA1),
+                "==" | "!=" => {
+                    let source = self.next()?;
+                    match self.register_of(&source.text) {
+                        Some(y) => self.emit(if op == "==" { 0x50 } else { 0x90 } | x, y << 4),
+                        None => {
+                            self.tokens.push_front(source);
+                            let n = self.byte()?;
+                            self.emit(if op == "==" { 0x30 } else { 0x40 } | x, n)
+                        },
+                    }
+                },
+                "<" | ">" | "<=" | ">=" => {
+                    if x == 0xF {
+                        return Err(format!("vf can not be compared with {}", op));
+                    }
+                    let source = self.next()?;
+                    match self.register_of(&source.text) {
+                        Some(y) => self.emit(0x8F, y << 4)?,
+                        None => {
+                            self.tokens.push_front(source);
+                            let n = self.byte()?;
+                            self.emit(0x6F, n)?;
+                        },
+                    }
+                    // vf is left 1 when vx >= the other side after 8FX7 and when vx <= it after 8FX5
+                    let subtract = if op == "<" || op == ">=" { 0x7 } else { 0x5 };
+                    self.emit(0x8F, (x << 4) | subtract)?;
+                    self.emit(if op == "<=" || op == ">=" { 0x4F } else { 0x3F }, 0x00)
+                },
+                _ => Err(format!("unknown comparison {}", op)),
+            }
+        }
+
+        fn expand_macro(&mut self, name: &str) -> Result<(), String> {
+            let param_count = self.macros[name].params.len();
+            let mut args: Vec<Token> = Vec::new();
+            for _ in 0..param_count {
+                args.push(self.next()?);
+            }
+            let definition = &self.macros[name];
+            let expansion: Vec<Token> = definition.body.iter()
+                .map(|token| match definition.params.iter().position(|param| *param == token.text && !token.quoted) {
+                    Some(idx) => args[idx].clone(),
+                    None => token.clone(),
+                })
+                .collect();
+            self.push_tokens(expansion);
+            Ok(())
+        }
+
+        // run the mode's body for each character, with VALUE its position in the mode's
+        // alphabet, CHAR its character code and INDEX its position in the string
+        fn expand_string_mode(&mut self, name: &str) -> Result<(), String> {
+            let text = self.string()?;
+            let mut expansion: Vec<Token> = Vec::new();
+            for (index, c) in text.chars().enumerate() {
+                let (value, body) = self.string_modes[name].get(&c)
+                    .ok_or(format!("string mode {} has no {:?}", name, c))?;
+                for token in body {
+                    let number = match token.text.as_str() {
+                        _ if token.quoted => None,
+                        "VALUE" => Some(*value),
+                        "CHAR" => Some(c as usize),
+                        "INDEX" => Some(index),
+                        _ => None,
+                    };
+                    expansion.push(match number {
+                        Some(number) => Token { text: number.to_string(), line: self.line, quoted: false },
+                        None => token.clone(),
+                    });
+                }
+            }
+            self.push_tokens(expansion);
+            Ok(())
+        }
+
+
+        // { expression }, evaluated right to left without precedence like Octo's :calc
+        fn calc(&mut self) -> Result<f64, String> {
+            self.expect("{")?;
+            let value = self.expression()?;
+            self.expect("}")?;
+            Ok(value)
+        }
+
+        fn expression(&mut self) -> Result<f64, String> {
+            let left = self.term()?;
+            match self.peek() {
+                Some(op) if BINARY_OPERATORS.contains(&op) => {
+                    let op = self.next()?.text;
+                    let right = self.expression()?;
+                    Ok(binary(&op, left, right))
+                },
+                _ => Ok(left),
+            }
+        }
+
+        fn term(&mut self) -> Result<f64, String> {
+            let token = self.next()?;
+            match token.text.as_str() {
+                "(" => {
+                    let value = self.expression()?;
+                    self.expect(")")?;
+                    Ok(value)
+                },
+                "@" => {
+                    let addr = self.term()?;
+                    Ok(self.memory.get(addr as usize).copied().unwrap_or(0) as f64)
+                },
+                op if UNARY_OPERATORS.contains(&op) => {
+                    let value = self.term()?;
+                    Ok(unary(op, value))
+                },
+                "HERE" => Ok(self.here as f64),
+                "PI" => Ok(consts::PI),
+                "E" => Ok(consts::E),
+                text => self.lookup(text).ok_or(format!("undefined name {}", text)),
+            }
+        }
+
+
+        // value of a number, constant or label that is already defined
+        fn lookup(&self, text: &str) -> Option<f64> {
+            parse_number(text)
+                .or_else(|| self.constants.get(text).copied())
+                .or_else(|| self.labels.get(text).map(|addr| *addr as f64))
+        }
+
+        fn value(&mut self) -> Result<f64, String> {
+            if self.peek() == Some("{") {
+                return self.calc();
+            }
+            let token = self.next()?;
+            self.lookup(&token.text).ok_or(format!("undefined name {}", token.text))
+        }
+
+        fn operand(&mut self) -> Result<Operand, String> {
+            if self.peek() == Some("{") {
+                return self.calc().map(Operand::Value);
+            }
+            let token = self.next()?;
+            match self.lookup(&token.text) {
+                Some(value) => Ok(Operand::Value(value)),
+                None if !token.quoted && self.register_of(&token.text).is_none() => Ok(Operand::Label(token.text)),
+                None => Err(format!("expected an address, found {}", token.text)),
+            }
+        }
+
+        fn byte(&mut self) -> Result<u8, String> {
+            let value = self.value()?;
+            to_byte(value)
+        }
+
+        fn nibble(&mut self) -> Result<u8, String> {
+            match self.value()? {
+                value if (0.0..16.0).contains(&value) => Ok(value as u8),
+                value => Err(format!("{} does not fit in a nibble", value)),
+            }
+        }
+
+        fn register(&mut self) -> Result<u8, String> {
+            let token = self.next()?;
+            self.register_of(&token.text).ok_or(format!("expected a register, found {}", token.text))
+        }
+
+        // v0 to vf in either case, or an alias for one
+        fn register_of(&self, text: &str) -> Option<u8> {
+            if let Some(register) = self.aliases.get(text) {
+                return Some(*register);
+            }
+            match text.strip_prefix(['v', 'V']) {
+                Some(digit) if digit.len() == 1 => u8::from_str_radix(digit, 16).ok(),
+                _ => None,
+            }
+        }
+
+        fn name(&mut self) -> Result<String, String> {
+            let token = self.next()?;
+            if token.quoted || parse_number(&token.text).is_some() || self.register_of(&token.text).is_some() {
+                return Err(format!("{} can not be used as a name", token.text));
+            }
+            Ok(token.text)
+        }
+
+        fn string(&mut self) -> Result<String, String> {
+            let token = self.next()?;
+            if !token.quoted {
+                return Err(format!("expected a string, found {}", token.text));
+            }
+            Ok(token.text)
+        }
+
+        fn define_label(&mut self, name: String, addr: usize) -> Result<(), String> {
+            if self.labels.contains_key(&name) || self.constants.contains_key(&name) {
+                return Err(format!("{} is already defined", name));
+            }
+            self.labels.insert(name, addr);
+            Ok(())
+        }
+
+
+        fn emit_byte(&mut self, byte: u8) -> Result<(), String> {
+            if self.here >= ADDRESS_SPACE {
+                return Err("program does not fit in 64 KiB".to_string());
+            }
+            self.memory[self.here] = byte;
+            self.here += 1;
+            self.end = self.end.max(self.here);
+            Ok(())
+        }
+
+        fn emit(&mut self, high: u8, low: u8) -> Result<(), String> {
+            self.emit_byte(high)?;
+            self.emit_byte(low)
+        }
+
+        // FXNN style instructions that take one register
+        fn register_instruction(&mut self, low: u8) -> Result<(), String> {
+            let x = self.register()?;
+            self.emit(0xF0 | x, low)
+        }
+
+        // an NNN instruction whose address may be a label defined further down
+        fn address_instruction(&mut self, high: u8) -> Result<(), String> {
+            let target = self.operand()?;
+            let at = self.here;
+            self.emit(high, 0x00)?;
+            self.resolve(at, Patch::Address12, target)
+        }
+
+        fn resolve(&mut self, addr: usize, patch: Patch, target: Operand) -> Result<(), String> {
+            match target {
+                Operand::Value(value) => self.apply(addr, patch, value),
+                Operand::Label(name) => {
+                    self.patches.push((addr, name, patch, self.line));
+                    Ok(())
+                },
+            }
+        }
+
+        fn apply(&mut self, addr: usize, patch: Patch, value: f64) -> Result<(), String> {
+            let limit = match patch {
+                Patch::Address12 | Patch::UnpackHigh(_) => 0x1000,
+                _ => ADDRESS_SPACE,
+            };
+            if !(0.0..limit as f64).contains(&value) {
+                return Err(format!("address {} is out of range", value));
+            }
+            let value = value as usize;
+            match patch {
+                Patch::Address12 => {
+                    self.memory[addr] = (self.memory[addr] & 0xF0) | (value >> 8) as u8;
+                    self.memory[addr + 1] = value as u8;
+                },
+                Patch::Address16 => {
+                    self.memory[addr] = (value >> 8) as u8;
+                    self.memory[addr + 1] = value as u8;
+                },
+                Patch::UnpackHigh(nibble) => self.memory[addr] = (nibble << 4) | (value >> 8) as u8,
+                Patch::LongHigh => self.memory[addr] = (value >> 8) as u8,
+                Patch::Low => self.memory[addr] = value as u8,
+            }
+            Ok(())
+        }
+
+
+        fn next(&mut self) -> Result<Token, String> {
+            let token = self.tokens.pop_front().ok_or("unexpected end of program")?;
+            self.line = token.line;
+            Ok(token)
+        }
+
+        fn peek(&self) -> Option<&str> {
+            self.tokens.front().filter(|token| !token.quoted).map(|token| token.text.as_str())
+        }
+
+        fn expect(&mut self, expected: &str) -> Result<(), String> {
+            let token = self.next()?;
+            if token.quoted || token.text != expected {
+                return Err(format!("expected {}, found {}", expected, token.text));
+            }
+            Ok(())
+        }
+
+        // the tokens between { and its matching }
+        fn braced(&mut self) -> Result<Vec<Token>, String> {
+            self.expect("{")?;
+            let mut body: Vec<Token> = Vec::new();
+            let mut depth = 0;
+            loop {
+                let token = self.next()?;
+                match token.text.as_str() {
+                    _ if token.quoted => {},
+                    "{" => depth += 1,
+                    "}" if depth == 0 => return Ok(body),
+                    "}" => depth -= 1,
+                    _ => {},
+                }
+                body.push(token);
+            }
+        }
+
+        fn push_tokens(&mut self, tokens: Vec<Token>) {
+            for token in tokens.into_iter().rev() {
+                self.tokens.push_front(token);
+            }
+        }
+    }
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    // assemble Octo source into a ROM loaded at x200
+    pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+        let mut assembler = Assembler {
+            tokens: tokenize(source)?,
+            memory: vec![0; ADDRESS_SPACE],
+            here: ROM_START,
+            end: ROM_START,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            string_modes: HashMap::new(),
+            patches: Vec::new(),
+            blocks: Vec::new(),
+            line: 1,
+        };
+        assembler.run().map_err(|e| format!("line {}: {}", assembler.line, e))
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    // whitespace separated tokens, # starts a comment that runs to the end of the line
+    // and "quoted strings" are a single token
+    fn tokenize(source: &str) -> Result<VecDeque<Token>, String> {
+        let mut tokens: VecDeque<Token> = VecDeque::new();
+        for (line_idx, line) in source.lines().enumerate() {
+            let mut chars = line.chars().peekable();
+            loop {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                match chars.peek() {
+                    None | Some('#') => break,
+                    Some('"') => {
+                        chars.next();
+                        let mut text = String::new();
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some('\\') => match chars.next() {
+                                    Some('n') => text.push('\n'),
+                                    Some('t') => text.push('\t'),
+                                    Some(c) => text.push(c),
+                                    None => return Err(format!("line {}: unterminated string", line_idx + 1)),
+                                },
+                                Some(c) => text.push(c),
+                                None => return Err(format!("line {}: unterminated string", line_idx + 1)),
+                            }
+                        }
+                        tokens.push_back(Token { text, line: line_idx + 1, quoted: true });
+                    },
+                    Some(_) => {
+                        let mut text = String::new();
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            text.push(c);
+                        }
+                        tokens.push_back(Token { text, line: line_idx + 1, quoted: false });
+                    },
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    // decimal, 0x hex and 0b binary integers, optionally negative
+    fn parse_number(text: &str) -> Option<f64> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            i64::from_str_radix(hex, 16).ok()?
+        } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+            i64::from_str_radix(binary, 2).ok()?
+        } else if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            digits.parse::<i64>().ok()?
+        } else {
+            return None;
+        };
+        Some(if negative { -value } else { value } as f64)
+    }
+
+    // bytes may be written signed
+    fn to_byte(value: f64) -> Result<u8, String> {
+        if !(-128.0..256.0).contains(&value) {
+            return Err(format!("{} does not fit in a byte", value));
+        }
+        Ok(value as i64 as u8)
+    }
+
+    fn unary(op: &str, value: f64) -> f64 {
+        match op {
+            "-" => -value,
+            "~" => !(value as i64) as f64,
+            "!" => if value == 0.0 { 1.0 } else { 0.0 },
+            "abs" => value.abs(),
+            "sqrt" => value.sqrt(),
+            "sin" => value.sin(),
+            "cos" => value.cos(),
+            "tan" => value.tan(),
+            "exp" => value.exp(),
+            "log" => value.ln(),
+            "sign" => if value == 0.0 { 0.0 } else { value.signum() },
+            "ceil" => value.ceil(),
+            _ => value.floor(),
+        }
+    }
+
+    fn binary(op: &str, left: f64, right: f64) -> f64 {
+        let (a, b) = (left as i64, right as i64);
+        let truth = |holds: bool| if holds { 1.0 } else { 0.0 };
+        match op {
+            "+" => left + right,
+            "-" => left - right,
+            "*" => left * right,
+            "/" => left / right,
+            "%" => left % right,
+            "&" => (a & b) as f64,
+            "|" => (a | b) as f64,
+            "^" => (a ^ b) as f64,
+            "<<" => a.checked_shl(b as u32).unwrap_or(0) as f64,
+            ">>" => a.checked_shr(b as u32).unwrap_or(0) as f64,
+            "pow" => left.powf(right),
+            "min" => left.min(right),
+            "max" => left.max(right),
+            "<" => truth(left < right),
+            "<=" => truth(left <= right),
+            ">" => truth(left > right),
+            ">=" => truth(left >= right),
+            "==" => truth(left == right),
+            _ => truth(left != right),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::octo::assemble;
+    use crate::cpu::cpu::CPU;
+
+    #[test]
+    fn should_assemble_instructions_and_patch_labels_defined_later() {
+        let rom = assemble("
+            : main
+                i := long data
+                jump0 table
+                :unpack 0xA table
+                :unpack long data
+                v3 := random 0x0F
+                v4 -= v5
+                save v2 - v6
+                bcd v1
+                scroll-down 4
+            : table
+                :pointer data
+            :org 0x300
+            : data
+                :byte { 2 * 3 + 1 }   # right to left, so 2 * 4
+        ").unwrap();
+
+        assert!(rom[..0x1C] == [
+            0x12, 0x02, 0xF0, 0x00, 0x03, 0x00, 0xB2, 0x1A, 0x60, 0xA2, 0x61, 0x1A, 0x60, 0x03,
+            0x61, 0x00, 0xC3, 0x0F, 0x84, 0x55, 0x52, 0x62, 0xF1, 0x33, 0x00, 0xC4, 0x03, 0x00,
+        ]);
+        assert!(rom.len() == 0x101);
+        assert!(rom[0x100] == 8);
+    }
+
+    #[test]
+    fn should_compile_conditions_into_skips_and_blocks_into_jumps() {
+        let rom = assemble("
+            : main
+                loop
+                    v0 += 1
+                    if v0 == v1 then v2 := 0
+                    if v0 > 5 begin
+                        v3 := 1
+                    else
+                        v3 := 2
+                    end
+                    while v0 <= v4
+                again
+        ").unwrap();
+
+        assert!(rom == vec![
+            0x12, 0x02, 0x70, 0x01, 0x90, 0x10, 0x62, 0x00, 0x6F, 0x05, 0x8F, 0x05, 0x3F, 0x00, 0x12, 0x14,
+            0x63, 0x01, 0x12, 0x16, 0x63, 0x02, 0x8F, 0x40, 0x8F, 0x05, 0x4F, 0x00, 0x12, 0x20, 0x12, 0x02,
+        ]);
+    }
+
+    #[test]
+    fn should_run_comparisons_the_way_they_read() {
+        let rom = assemble("
+            : main
+                v0 := 3
+                v1 := 7
+                if v0 < v1 then v5 += 1
+                if v0 <= 3 then v5 += 2
+                if v1 > v0 then v5 += 4
+                if v1 >= 8 then v5 += 8
+                if v0 != 3 then v5 += 16
+                if v1 == v1 then v5 += 32
+            : halt
+                jump halt
+        ").unwrap();
+        let mut cpu = CPU::new();
+        cpu.load_rom(rom).unwrap();
+
+        for _ in 0..40 { cpu.step(); }
+
+        assert!(cpu.registers[5] == 1 + 2 + 4 + 32);
+    }
+
+    #[test]
+    fn should_expand_macros_constants_and_string_modes() {
+        let rom = assemble("
+            :const BASE 0x10
+            :calc DOUBLE { BASE * 2 }
+            :alias counter v7
+            :macro add-twice REG N { REG += N REG += N }
+            :stringmode text \"AB\" { :byte { VALUE + BASE } }
+            : main
+                add-twice counter DOUBLE
+                text \"BAB\"
+                :next target v1 := 0
+                i := target
+        ").unwrap();
+
+        assert!(rom == vec![0x12, 0x02, 0x77, 0x20, 0x77, 0x20, 0x11, 0x10, 0x11, 0x61, 0x00, 0xA2, 0x0A]);
+    }
+
+    #[test]
+    fn should_report_errors_with_their_line() {
+        assert!(assemble(": start\n clear").unwrap_err().contains("no main label"));
+        assert!(assemble(": main\n jump nowhere").unwrap_err() == "line 2: undefined name nowhere");
+        assert!(assemble(": main\n v0 := 300").unwrap_err().starts_with("line 2:"));
+        assert!(assemble(": main\n if v0 == 1 clear").unwrap_err().contains("without then or begin"));
+        assert!(assemble(": main\n loop clear").unwrap_err().contains("loop without again"));
+    }
+}
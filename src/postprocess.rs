@@ -0,0 +1,248 @@
+pub mod postprocess {
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const BYTES_PER_RGBA_PIXEL: usize = 4;
+    const DEFAULT_SCANLINES       : f32 = 0.35;
+    const DEFAULT_GRID            : f32 = 0.25;
+    const DEFAULT_BLOOM           : f32 = 0.3;
+    const DEFAULT_CURVATURE       : f32 = 0.08;
+
+    // -------------------------
+    // --- CRT FILTER CONFIG ---
+    // -------------------------
+
+    // strengths are 0.0 (no effect) to 1.0 (full effect)
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub struct CrtSettings {
+        pub enabled: bool,
+        pub scanlines: f32, // darkening of every other row of output pixels
+        pub grid: f32,      // darkening of the gap between neighbouring CHIP-8 pixels
+        pub bloom: f32,     // glow of lit pixels onto their surroundings
+        pub curvature: f32, // barrel distortion of the whole picture
+    }
+
+    impl Default for CrtSettings {
+        fn default() -> Self {
+            CrtSettings {
+                enabled: false,
+                scanlines: DEFAULT_SCANLINES,
+                grid: DEFAULT_GRID,
+                bloom: DEFAULT_BLOOM,
+                curvature: DEFAULT_CURVATURE,
+            }
+        }
+    }
+
+    impl CrtSettings {
+
+        // accepts "on" for the defaults or a list like "scanlines=0.4,grid=0,bloom=0.2,curvature=0.1"
+        // where omitted options keep their defaults
+        pub fn parse(value: &str) -> Option<CrtSettings> {
+            let mut settings = CrtSettings { enabled: true, ..CrtSettings::default() };
+            if value == "on" {
+                return Some(settings);
+            }
+            if value == "off" {
+                return Some(CrtSettings::default());
+            }
+
+            for option in value.split(',') {
+                let (name, strength) = option.split_once('=')?;
+                let strength = strength.parse::<f32>().ok().filter(|strength| (0.0..=1.0).contains(strength))?;
+                match name {
+                    "scanlines" => settings.scanlines = strength,
+                    "grid" => settings.grid = strength,
+                    "bloom" => settings.bloom = strength,
+                    "curvature" => settings.curvature = strength,
+                    _ => return None,
+                }
+            }
+            Some(settings)
+        }
+    }
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    // nearest-neighbour upscale of a packed RGB image into RGBA
+    pub fn upscale_rgb_to_rgba(rgb: &[u8], width: usize, height: usize, factor: usize) -> Vec<u8> {
+        let out_width = width * factor;
+        let mut rgba: Vec<u8> = vec![0; out_width * height * factor * BYTES_PER_RGBA_PIXEL];
+        for out_y in 0..height * factor {
+            for out_x in 0..out_width {
+                let src = ((out_y / factor) * width + out_x / factor) * 3;
+                let dst = (out_y * out_width + out_x) * BYTES_PER_RGBA_PIXEL;
+                rgba[dst..dst + 3].copy_from_slice(&rgb[src..src + 3]);
+                rgba[dst + 3] = u8::MAX;
+            }
+        }
+        rgba
+    }
+
+    // run the enabled CRT stages over an RGBA image where every CHIP-8 pixel
+    // covers a cell_size x cell_size block
+    pub fn apply_crt(rgba: &mut Vec<u8>, width: usize, height: usize, cell_size: usize, settings: &CrtSettings) {
+        if !settings.enabled { return; }
+
+        if settings.bloom > 0.0 {
+            apply_bloom(rgba, width, height, cell_size / 2, settings.bloom);
+        }
+        if settings.grid > 0.0 || settings.scanlines > 0.0 {
+            apply_masks(rgba, width, height, cell_size, settings);
+        }
+        if settings.curvature > 0.0 {
+            *rgba = apply_curvature(rgba, width, height, settings.curvature);
+        }
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    fn scale_pixel(rgba: &mut [u8], offset: usize, factor: f32) {
+        for channel in rgba[offset..offset + 3].iter_mut() {
+            *channel = (*channel as f32 * factor) as u8;
+        }
+    }
+
+    // scanlines darken odd output rows and the grid darkens the last row and column
+    // of each CHIP-8 pixel block
+    fn apply_masks(rgba: &mut [u8], width: usize, height: usize, cell_size: usize, settings: &CrtSettings) {
+        for y in 0..height {
+            for x in 0..width {
+                let mut factor = 1.0;
+                if y % 2 == 1 {
+                    factor *= 1.0 - settings.scanlines;
+                }
+                if cell_size > 1 && (x % cell_size == cell_size - 1 || y % cell_size == cell_size - 1) {
+                    factor *= 1.0 - settings.grid;
+                }
+                if factor < 1.0 {
+                    scale_pixel(rgba, (y * width + x) * BYTES_PER_RGBA_PIXEL, factor);
+                }
+            }
+        }
+    }
+
+    // add a box-blurred copy of the image on top of itself
+    fn apply_bloom(rgba: &mut [u8], width: usize, height: usize, radius: usize, strength: f32) {
+        let radius = radius.max(1);
+        let blurred = box_blur(&box_blur(rgba, width, height, radius, true), width, height, radius, false);
+        for (channel, glow) in rgba.iter_mut().zip(blurred.iter()) {
+            *channel = (*channel as f32 + *glow as f32 * strength).min(u8::MAX as f32) as u8;
+        }
+    }
+
+    // one pass of a separable box blur, horizontal or vertical, using a running sum
+    fn box_blur(rgba: &[u8], width: usize, height: usize, radius: usize, horizontal: bool) -> Vec<u8> {
+        let mut out: Vec<u8> = rgba.to_vec();
+        let (lines, line_len) = if horizontal { (height, width) } else { (width, height) };
+        let index = |line: usize, pos: usize| -> usize {
+            let (x, y) = if horizontal { (pos, line) } else { (line, pos) };
+            (y * width + x) * BYTES_PER_RGBA_PIXEL
+        };
+        let window = (2 * radius + 1) as u32;
+
+        for line in 0..lines {
+            for channel in 0..3 {
+                let mut sum: u32 = 0;
+                for pos in 0..=radius.min(line_len - 1) {
+                    sum += rgba[index(line, pos) + channel] as u32;
+                }
+                for pos in 0..line_len {
+                    out[index(line, pos) + channel] = (sum / window) as u8;
+                    if pos + radius + 1 < line_len {
+                        sum += rgba[index(line, pos + radius + 1) + channel] as u32;
+                    }
+                    if pos >= radius {
+                        sum -= rgba[index(line, pos - radius) + channel] as u32;
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    // barrel distortion: every output pixel samples the source further from the
+    // centre the further it is from the centre itself, corners fall off to black
+    fn apply_curvature(rgba: &[u8], width: usize, height: usize, curvature: f32) -> Vec<u8> {
+        let mut out: Vec<u8> = vec![0; rgba.len()];
+        let half_width = width as f32 / 2.0;
+        let half_height = height as f32 / 2.0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let u = (x as f32 + 0.5 - half_width) / half_width;
+                let v = (y as f32 + 0.5 - half_height) / half_height;
+                let distortion = 1.0 + curvature * (u * u + v * v);
+                let src_x = (u * distortion * half_width + half_width).floor();
+                let src_y = (v * distortion * half_height + half_height).floor();
+
+                let dst = (y * width + x) * BYTES_PER_RGBA_PIXEL;
+                if src_x < 0.0 || src_y < 0.0 || src_x >= width as f32 || src_y >= height as f32 {
+                    out[dst + 3] = u8::MAX;
+                    continue;
+                }
+                let src = (src_y as usize * width + src_x as usize) * BYTES_PER_RGBA_PIXEL;
+                out[dst..dst + BYTES_PER_RGBA_PIXEL].copy_from_slice(&rgba[src..src + BYTES_PER_RGBA_PIXEL]);
+            }
+        }
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::postprocess::{apply_crt, upscale_rgb_to_rgba, CrtSettings};
+
+    #[test]
+    fn should_upscale_rgb_into_opaque_rgba_blocks() {
+        let rgba = upscale_rgb_to_rgba(&[10, 20, 30, 40, 50, 60], 2, 1, 2);
+
+        assert!(rgba.len() == 4 * 2 * 4);
+        assert!(rgba[0..8] == [10, 20, 30, 255, 10, 20, 30, 255]);
+        assert!(rgba[8..12] == [40, 50, 60, 255]);
+        assert!(rgba[16..20] == [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn should_darken_scanlines_and_grid_gaps() {
+        let settings = CrtSettings { enabled: true, scanlines: 0.5, grid: 0.5, bloom: 0.0, curvature: 0.0 };
+        let mut rgba = upscale_rgb_to_rgba(&[200, 200, 200], 1, 1, 4);
+
+        apply_crt(&mut rgba, 4, 4, 4, &settings);
+
+        let red = |x: usize, y: usize| rgba[(y * 4 + x) * 4];
+        assert!(red(0, 0) == 200);
+        assert!(red(0, 1) == 100);
+        assert!(red(3, 0) == 100);
+        assert!(red(3, 3) == 50);
+    }
+
+    #[test]
+    fn should_leave_image_untouched_when_disabled() {
+        let mut rgba = upscale_rgb_to_rgba(&[200, 100, 50], 1, 1, 4);
+        let original = rgba.clone();
+
+        apply_crt(&mut rgba, 4, 4, 4, &CrtSettings::default());
+
+        assert!(rgba == original);
+    }
+
+    #[test]
+    fn should_parse_crt_options_and_keep_defaults_for_the_rest() {
+        let settings = CrtSettings::parse("scanlines=0.5,curvature=0").unwrap();
+
+        assert!(settings.enabled);
+        assert!(settings.scanlines == 0.5);
+        assert!(settings.curvature == 0.0);
+        assert!(settings.grid == CrtSettings::default().grid);
+        assert!(CrtSettings::parse("scanlines=2").is_none());
+        assert!(CrtSettings::parse("glow=0.5").is_none());
+    }
+}
@@ -0,0 +1,212 @@
+pub mod rom {
+
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant, SystemTime};
+
+    use crate::cartridge::cartridge::Cartridge;
+    use crate::cpu::cpu::{Platform, Quirks};
+    use crate::octo::octo::assemble;
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const ROM_EXTENSIONS: [&str; 8] = ["ch8", "c8", "sc8", "xo8", "bin", "hex", "txt", "gif"];
+    const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    // a ROM and the platform and quirks it asks for, from its extension or its Octo cartridge options
+    pub struct RomImage {
+        pub bytes: Vec<u8>,
+        pub platform: Option<Platform>,
+        pub quirks: Option<Quirks>,
+    }
+
+    // polls a ROM file's modification time so it can be reloaded after it was rebuilt
+    pub struct RomWatcher {
+        path: PathBuf,
+        modified: Option<SystemTime>,
+        next_poll: Instant,
+    }
+
+    impl RomWatcher {
+
+        pub fn new(path: &Path, now: Instant) -> RomWatcher {
+            RomWatcher {
+                path: path.to_path_buf(),
+                modified: modified_time(path),
+                next_poll: now + WATCH_POLL_INTERVAL,
+            }
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        // the ROM read again once its modification time changed, a ROM that fails to
+        // read (e.g. while the assembler is still writing it) is retried on its next change
+        pub fn poll(&mut self, now: Instant) -> Option<Result<Vec<u8>, String>> {
+            if now < self.next_poll {
+                return None;
+            }
+            self.next_poll = now + WATCH_POLL_INTERVAL;
+
+            let modified = modified_time(&self.path);
+            if modified.is_none() || modified == self.modified {
+                return None;
+            }
+            self.modified = modified;
+            Some(read_rom(&self.path))
+        }
+    }
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    pub fn is_rom_path(path: &Path) -> bool {
+        extension(path).map(|ext| ROM_EXTENSIONS.contains(&ext.as_str())).unwrap_or(false)
+    }
+
+    // platform implied by the file extension, plain CHIP-8 files leave the choice to the user
+    pub fn platform_for_path(path: &Path) -> Option<Platform> {
+        match extension(path)?.as_str() {
+            "sc8" => Some(Platform::Schip),
+            "xo8" => Some(Platform::Xochip),
+            _ => None,
+        }
+    }
+
+    pub fn read_rom(path: &Path) -> Result<Vec<u8>, String> {
+        read_rom_image(path).map(|image| image.bytes)
+    }
+
+    // read a ROM as binary, as a hex text dump for .hex and .txt files, or assemble the
+    // program of an Octo cartridge GIF, whatever its extension
+    pub fn read_rom_image(path: &Path) -> Result<RomImage, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if Cartridge::is_cartridge(&bytes) {
+            let cartridge = Cartridge::decode(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let rom = assemble(&cartridge.program).map_err(|e| format!("{}: {}", path.display(), e))?;
+            return Ok(RomImage { bytes: rom, platform: cartridge.platform(), quirks: Some(cartridge.quirks()) });
+        }
+
+        let rom = match extension(path).as_deref() {
+            Some("hex") | Some("txt") => {
+                let text = String::from_utf8(bytes).map_err(|_| format!("{}: hex dump is not text", path.display()))?;
+                parse_hex_dump(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+            },
+            _ => bytes,
+        };
+        if rom.is_empty() {
+            return Err(format!("{}: ROM is empty", path.display()));
+        }
+        Ok(RomImage { bytes: rom, platform: platform_for_path(path), quirks: None })
+    }
+
+    // bytes written as pairs of hex digits, separated by whitespace or commas, with optional
+    // 0x prefixes, "0200:" style address columns and # or ; comments
+    pub fn parse_hex_dump(text: &str) -> Result<Vec<u8>, String> {
+        let mut rom: Vec<u8> = Vec::new();
+        for (line_idx, line) in text.lines().enumerate() {
+            let line = line.split(['#', ';']).next().unwrap_or("");
+            for token in line.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()) {
+                if token.ends_with(':') { continue; }
+
+                let digits = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+                if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("line {}: invalid hex bytes {:?}", line_idx + 1, token));
+                }
+                for pair_idx in (0..digits.len()).step_by(2) {
+                    rom.push(u8::from_str_radix(&digits[pair_idx..pair_idx + 2], 16).unwrap());
+                }
+            }
+        }
+        Ok(rom)
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    fn extension(path: &Path) -> Option<String> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::path::Path;
+    use std::time::{Duration, Instant, SystemTime};
+
+    use super::rom::{is_rom_path, parse_hex_dump, platform_for_path, read_rom_image, RomWatcher};
+    use crate::cpu::cpu::Platform;
+
+    #[test]
+    fn should_accept_rom_extensions_and_pick_platform() {
+        assert!(is_rom_path(Path::new("games/PONG.CH8")));
+        assert!(is_rom_path(Path::new("car.xo8")));
+        assert!(!is_rom_path(Path::new("notes.md")));
+        assert!(is_rom_path(Path::new("cart.gif")));
+        assert!(!is_rom_path(Path::new("ch8")));
+
+        assert!(platform_for_path(Path::new("ant.sc8")) == Some(Platform::Schip));
+        assert!(platform_for_path(Path::new("car.xo8")) == Some(Platform::Xochip));
+        assert!(platform_for_path(Path::new("pong.ch8")).is_none());
+    }
+
+    #[test]
+    fn should_parse_hex_dumps_with_addresses_and_comments() {
+        let rom = parse_hex_dump("0200: 00E0 6A02 # clear and load\n0x12, 0x04 ; loop\n").unwrap();
+
+        assert!(rom == vec![0x00, 0xE0, 0x6A, 0x02, 0x12, 0x04]);
+        assert!(parse_hex_dump("00E").is_err());
+        assert!(parse_hex_dump("zz").is_err());
+    }
+
+    #[test]
+    fn should_assemble_octo_cartridge_and_pick_its_platform() {
+        let image = read_rom_image(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/Smiley [Octo cartridge].gif"))).unwrap();
+
+        assert!(image.bytes == vec![
+            0x12, 0x18, 0x3C, 0x42, 0xA5, 0x81, 0xA5, 0x99, 0x42, 0x3C, 0x63, 0x02, 0xF3, 0x15, 0xF3, 0x07,
+            0x43, 0x00, 0x12, 0x16, 0x12, 0x0E, 0x00, 0xEE, 0x60, 0x1C, 0x61, 0x0C, 0xA2, 0x02, 0xD0, 0x18,
+            0x22, 0x0A, 0xD0, 0x18, 0x62, 0x07, 0xE2, 0xA1, 0x70, 0xFF, 0x62, 0x09, 0xE2, 0xA1, 0x70, 0x01,
+            0x62, 0x05, 0xE2, 0xA1, 0x71, 0xFF, 0x62, 0x08, 0xE2, 0xA1, 0x71, 0x01, 0xD0, 0x18, 0x12, 0x20,
+        ]);
+        assert!(image.platform == Some(Platform::Schip));
+        assert!(image.quirks.map(|quirks| quirks.shift_in_place) == Some(true));
+    }
+
+    #[test]
+    fn should_reload_rom_only_after_its_modification_time_changes() {
+        let path = std::env::temp_dir().join(format!("chip8-watch-{}.ch8", std::process::id()));
+        fs::write(&path, [0x00, 0xE0]).unwrap();
+        let start = Instant::now();
+        let mut watcher = RomWatcher::new(&path, start);
+        let later = start + Duration::from_secs(1);
+
+        assert!(watcher.poll(later).is_none());
+
+        fs::write(&path, [0x12, 0x00]).unwrap();
+        File::options().write(true).open(&path).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert!(watcher.poll(start).is_none());
+        assert!(watcher.poll(later + Duration::from_secs(1)).unwrap().unwrap() == vec![0x12, 0x00]);
+        assert!(watcher.poll(later + Duration::from_secs(2)).is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+pub mod scheduler {
+
+    use std::time::{Duration, Instant};
+
+    use crate::cpu::cpu::CPU;
+    use crate::debugger::debugger::DebugState;
+    use crate::disasm::disasm::read_opcode;
+    use crate::timing::timing::vip_cycles;
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    pub const FRAME_RATE: u32 = 60;
+    pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 1;
+    const MAX_FRAMES_BEHIND: u32 = 4; // frames run back to back before the scheduler gives up catching up
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    // how much work a frame holds, a fixed number of instructions or a number of VIP
+    // machine cycles where each instruction is charged its own cost
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum FrameBudget {
+        Instructions(usize),
+        VipCycles(u32),
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum FrameOutcome {
+        Completed,
+        HitBreakpoint,
+    }
+
+    // paces the emulator in 60 Hz frames, each frame is a vblank, a timer tick and
+    // as many instructions as the budget allows
+    pub struct FrameScheduler {
+        pub budget: FrameBudget,
+        frame_duration: Duration,
+        next_frame: Instant,
+        cycle_balance: i64, // cycles left over from earlier frames, negative after an instruction overran its frame
+    }
+
+    impl FrameScheduler {
+
+        pub fn new(budget: FrameBudget, now: Instant) -> FrameScheduler {
+            let frame_duration = Duration::from_secs(1) / FRAME_RATE;
+            FrameScheduler {
+                budget,
+                frame_duration,
+                next_frame: now + frame_duration,
+                cycle_balance: 0,
+            }
+        }
+
+        // drop cycle debt or savings left over from the previous ROM
+        pub fn reset_for_reload(&mut self) {
+            self.cycle_balance = 0;
+        }
+
+        // number of frames that should run by now, after a long stall (e.g. a window
+        // drag) the missed frames are dropped instead of run all at once
+        pub fn frames_due(&mut self, now: Instant) -> u32 {
+            let mut frames = 0;
+            while self.next_frame <= now && frames < MAX_FRAMES_BEHIND {
+                self.next_frame += self.frame_duration;
+                frames += 1;
+            }
+            if self.next_frame <= now {
+                self.next_frame = now + self.frame_duration;
+            }
+            frames
+        }
+
+        // run one frame, stopping early when a breakpoint is reached or a DXYN is
+        // waiting for the next vblank
+        pub fn run_frame(&mut self, cpu: &mut CPU, debug_state: &mut DebugState) -> FrameOutcome {
+            let mut resuming = cpu.is_display_waiting();
+            cpu.vblank();
+            cpu.update_timers();
+
+            // an instruction that costs more than a frame carries its debt into the next
+            // ones, unused cycles are not saved up beyond one frame
+            let mut instructions_left = match self.budget {
+                FrameBudget::Instructions(count) => count,
+                FrameBudget::VipCycles(cycles) => {
+                    self.cycle_balance = (self.cycle_balance + cycles as i64).min(cycles as i64);
+                    usize::MAX
+                },
+            };
+
+            while instructions_left > 0 && self.cycle_balance >= 0 {
+                // a DXYN stalled last frame finishes without stopping at its breakpoint again
+                if !resuming && debug_state.should_break(cpu.pc) {
+                    return FrameOutcome::HitBreakpoint;
+                }
+                resuming = false;
+
+                // the cost depends on the state before the instruction runs
+                let cycles = match self.budget {
+                    FrameBudget::VipCycles(_) => vip_cycles(read_opcode(&cpu.memory, cpu.pc), cpu),
+                    FrameBudget::Instructions(_) => 0,
+                };
+                cpu.step();
+                instructions_left -= 1;
+
+                // a stalled DXYN gives up the rest of its frame and is only charged once it
+                // draws after the vblank
+                if cpu.is_display_waiting() {
+                    self.cycle_balance = self.cycle_balance.min(0);
+                    break;
+                }
+                self.cycle_balance -= cycles as i64;
+            }
+            FrameOutcome::Completed
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::scheduler::{FrameBudget, FrameOutcome, FrameScheduler};
+    use crate::cpu::cpu::CPU;
+    use crate::debugger::debugger::DebugState;
+
+    // two sprite draws followed by a jump to itself
+    const TWO_DRAWS_ROM: [u8; 6] = [0xD0, 0x11, 0xD0, 0x11, 0x12, 0x04];
+
+    #[test]
+    fn should_count_due_frames_and_drop_long_backlogs() {
+        let start = Instant::now();
+        let mut scheduler = FrameScheduler::new(FrameBudget::Instructions(1), start);
+
+        assert!(scheduler.frames_due(start) == 0);
+        assert!(scheduler.frames_due(start + Duration::from_millis(35)) == 2);
+        assert!(scheduler.frames_due(start + Duration::from_secs(2)) == 4);
+        assert!(scheduler.frames_due(start + Duration::from_secs(2)) == 0);
+    }
+
+    #[test]
+    fn should_draw_one_sprite_per_frame_with_display_wait() {
+        let mut cpu = CPU::new();
+        cpu.load_rom(TWO_DRAWS_ROM.to_vec()).unwrap();
+        cpu.quirks.display_wait = true;
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::Instructions(10), Instant::now());
+
+        assert!(scheduler.run_frame(&mut cpu, &mut debug_state) == FrameOutcome::Completed);
+        assert!(cpu.pc == 0x202);
+        assert!(cpu.is_display_waiting());
+
+        assert!(scheduler.run_frame(&mut cpu, &mut debug_state) == FrameOutcome::Completed);
+        assert!(cpu.pc == 0x204);
+    }
+
+    #[test]
+    fn should_run_every_instruction_without_display_wait() {
+        let mut cpu = CPU::new();
+        cpu.load_rom(TWO_DRAWS_ROM.to_vec()).unwrap();
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::Instructions(2), Instant::now());
+
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x204);
+        assert!(!cpu.is_display_waiting());
+    }
+
+    #[test]
+    fn should_finish_a_stalled_draw_without_stopping_at_its_breakpoint() {
+        let mut cpu = CPU::new();
+        cpu.load_rom(TWO_DRAWS_ROM.to_vec()).unwrap();
+        cpu.quirks.display_wait = true;
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::Instructions(10), Instant::now());
+
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x202);
+
+        debug_state.toggle_breakpoint(0x202);
+        debug_state.toggle_breakpoint(0x204);
+        assert!(scheduler.run_frame(&mut cpu, &mut debug_state) == FrameOutcome::HitBreakpoint);
+        assert!(cpu.pc == 0x204);
+    }
+
+    #[test]
+    fn should_run_instructions_until_the_cycle_budget_is_spent() {
+        let mut cpu = CPU::new();
+        cpu.load_rom([0x60, 0x01].repeat(64)).unwrap();
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::VipCycles(100), Instant::now());
+
+        // 6XNN costs 26 cycles, the fourth one overruns the budget
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        let executed = (cpu.pc - 0x200) / 2;
+        assert!(executed == 4);
+    }
+
+    #[test]
+    fn should_charge_a_stalled_draw_only_when_it_runs() {
+        // an aligned one row DXYN costs 112 cycles, the second one stalls for the vblank
+        let mut cpu = CPU::new();
+        let mut rom = TWO_DRAWS_ROM[..4].to_vec();
+        rom.extend([0x60, 0x01].repeat(4));
+        cpu.load_rom(rom).unwrap();
+        cpu.quirks.display_wait = true;
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::VipCycles(150), Instant::now());
+
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x202 && cpu.is_display_waiting());
+
+        // the draw leaves 38 cycles, enough to start two 6XNN
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x208);
+    }
+
+    #[test]
+    fn should_carry_cycle_debt_of_a_long_instruction_into_later_frames() {
+        // FF55 costs more than two frames of 100 cycles
+        let mut cpu = CPU::new();
+        cpu.load_rom(vec![0xFF, 0x55, 0x60, 0x01, 0x60, 0x01]).unwrap();
+        cpu.reg_i = 0x300;
+        let mut debug_state = DebugState::new();
+        let mut scheduler = FrameScheduler::new(FrameBudget::VipCycles(100), Instant::now());
+
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x202);
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc == 0x202);
+
+        // a reload starts without the debt
+        scheduler.reset_for_reload();
+        scheduler.run_frame(&mut cpu, &mut debug_state);
+        assert!(cpu.pc > 0x202);
+    }
+}
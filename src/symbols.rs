@@ -0,0 +1,107 @@
+pub mod symbols {
+
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+
+    // -----------------------
+    // --- STRUCTS / ENUMS ---
+    // -----------------------
+
+    // labels for memory addresses, loaded from a symbol file where each line is
+    // either "label = 0x200" or "0x200 label", blank lines and # comments are skipped
+    #[derive(Clone, Default, Debug)]
+    pub struct SymbolTable {
+        labels: BTreeMap<usize, String>,
+    }
+
+    impl SymbolTable {
+
+        pub fn parse(text: &str) -> Result<SymbolTable, String> {
+            let mut labels: BTreeMap<usize, String> = BTreeMap::new();
+            for (line_idx, line) in text.lines().enumerate() {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty() { continue; }
+
+                let (name, addr) = match line.split_once('=') {
+                    Some((name, addr)) => (name.trim(), addr.trim()),
+                    None => match line.split_once(char::is_whitespace) {
+                        Some((addr, name)) => (name.trim(), addr.trim()),
+                        None => return Err(format!("line {}: expected a label and an address", line_idx + 1)),
+                    },
+                };
+                let addr = parse_address(addr)
+                    .ok_or(format!("line {}: invalid address {:?}", line_idx + 1, addr))?;
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(format!("line {}: invalid label {:?}", line_idx + 1, name));
+                }
+                labels.insert(addr, name.to_string());
+            }
+            Ok(SymbolTable { labels })
+        }
+
+        pub fn load(path: &Path) -> Result<SymbolTable, String> {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            SymbolTable::parse(&text)
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.labels.is_empty()
+        }
+
+        // label placed exactly at addr
+        pub fn label(&self, addr: usize) -> Option<&str> {
+            self.labels.get(&addr).map(|label| label.as_str())
+        }
+
+        // nearest label at or before addr, as "label" or "label+offset"
+        pub fn describe(&self, addr: usize) -> Option<String> {
+            let (label_addr, label) = self.labels.range(..=addr).next_back()?;
+            match addr - label_addr {
+                0 => Some(label.clone()),
+                offset => Some(format!("{}+{:#x}", label, offset)),
+            }
+        }
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    // addresses are hex with an optional 0x or $ prefix
+    fn parse_address(text: &str) -> Option<usize> {
+        let digits = text.strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .or_else(|| text.strip_prefix('$'))
+            .unwrap_or(text);
+        usize::from_str_radix(digits, 16).ok()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::symbols::SymbolTable;
+
+    #[test]
+    fn should_parse_both_symbol_line_formats() {
+        let table = SymbolTable::parse("# sprites\nmain = 0x200\n0x2a0 draw_player\n\n$300 data  # tiles\n").unwrap();
+
+        assert!(table.label(0x200) == Some("main"));
+        assert!(table.label(0x2a0) == Some("draw_player"));
+        assert!(table.label(0x300) == Some("data"));
+        assert!(table.label(0x202).is_none());
+        assert!(SymbolTable::parse("main = 0xZZZ").is_err());
+        assert!(SymbolTable::parse("main").is_err());
+    }
+
+    #[test]
+    fn should_describe_address_relative_to_nearest_label() {
+        let table = SymbolTable::parse("main = 200\nloop = 20a").unwrap();
+
+        assert!(table.describe(0x200) == Some("main".to_string()));
+        assert!(table.describe(0x206) == Some("main+0x6".to_string()));
+        assert!(table.describe(0x20c) == Some("loop+0x2".to_string()));
+        assert!(table.describe(0x100).is_none());
+    }
+}
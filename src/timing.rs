@@ -0,0 +1,133 @@
+pub mod timing {
+
+    use crate::cpu::cpu::{CPU, SCREEN_HEIGHT};
+
+    // -----------------
+    // --- CONSTANTS ---
+    // -----------------
+
+    // the VIP's 1802 runs at 1.76 MHz with 8 clocks per machine cycle, 3668 machine
+    // cycles per 60 Hz frame of which the display interrupt and its DMA of 128 lines
+    // of 8 bytes take about 1090, leaving the rest for the interpreter
+    pub const VIP_CYCLES_PER_FRAME: u32 = 3668 - 1090;
+
+    const FETCH_CYCLES: u32 = 20;   // fetch and decode, paid by every instruction
+    const SKIP_CYCLES: u32 = 4;     // extra cost of taking a conditional skip
+    const SPRITE_SETUP_CYCLES: u32 = 68;
+    const SPRITE_ROW_CYCLES: u32 = 24;         // a row that starts on a display byte boundary
+    const SPRITE_SHIFTED_ROW_CYCLES: u32 = 38; // a row that has to be shifted across two display bytes
+
+    // ------------------------
+    // --- PUBLIC FUNCTIONS ---
+    // ------------------------
+
+    // approximate machine cycles the VIP interpreter spends on instruction, given the
+    // state right before it runs, costs that depend on operands (skips, sprite size and
+    // alignment, BCD digits, register counts) are worked out from cpu
+    //
+    // the table is an estimate, not a cycle-exact model: the costs are rounded averages
+    // of the interpreter's routines that keep their relative order (e.g. every 8XYN is
+    // charged the same 44 cycles although the logic ops are cheaper than shifts), good
+    // enough to pace programs like a VIP but not to reproduce its timing to the cycle
+    pub fn vip_cycles(instruction: usize, cpu: &CPU) -> u32 {
+        let x = (instruction & 0x0F00) >> 8;
+        let y = (instruction & 0x00F0) >> 4;
+        let n = instruction & 0x000F;
+        let nn = (instruction & 0x00FF) as u8;
+        let vx = cpu.registers[x];
+        let vy = cpu.registers[y];
+        let skip = |taken: bool| if taken { SKIP_CYCLES } else { 0 };
+
+        let cost = match instruction & 0xF000 {
+            0x0000 => match instruction {
+                0x00E0 => 24,
+                0x00EE => 10,
+                _ => 12, // the call into the machine code routine, not the routine itself
+            },
+            0x1000 => 12,
+            0x2000 => 26,
+            0x3000 => 10 + skip(vx == nn),
+            0x4000 => 10 + skip(vx != nn),
+            0x5000 => 14 + skip(vx == vy),
+            0x6000 => 6,
+            0x7000 => 10,
+            0x8000 => 44,
+            0x9000 => 14 + skip(vx != vy),
+            0xA000 => 12,
+            0xB000 => 22,
+            0xC000 => 36,
+            0xD000 => sprite_cycles(n, vx, vy),
+            0xE000 => 14 + match nn {
+                0x9E => skip(cpu.is_key_pressed(vx as usize & 0xF)),
+                0xA1 => skip(!cpu.is_key_pressed(vx as usize & 0xF)),
+                _ => 0,
+            },
+            _ => match nn {
+                0x0A => 19, // one poll of the keypad, paid again on every step while waiting
+                0x1E => 16,
+                0x29 => 20,
+                0x33 => 84 + 16 * (vx / 100 + vx / 10 % 10 + vx % 10) as u32,
+                0x55 | 0x65 => 14 + 14 * (x as u32 + 1),
+                _ => 10,
+            },
+        };
+        FETCH_CYCLES + cost
+    }
+
+    // -------------------------
+    // --- PRIVATE FUNCTIONS ---
+    // -------------------------
+
+    // rows drawn off the bottom of the screen still cost their setup, rows that do not
+    // start on a byte boundary are shifted into two display bytes
+    fn sprite_cycles(rows: usize, vx: u8, vy: u8) -> u32 {
+        let visible_rows = rows.min(SCREEN_HEIGHT - vy as usize % SCREEN_HEIGHT) as u32;
+        let row_cycles = match vx % 8 {
+            0 => SPRITE_ROW_CYCLES,
+            _ => SPRITE_SHIFTED_ROW_CYCLES,
+        };
+        SPRITE_SETUP_CYCLES + visible_rows * row_cycles
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::timing::vip_cycles;
+    use crate::cpu::cpu::CPU;
+
+    #[test]
+    fn should_charge_extra_for_taken_skips() {
+        let mut cpu = CPU::new();
+        cpu.registers[1] = 0x22;
+
+        assert!(vip_cycles(0x3122, &cpu) > vip_cycles(0x3123, &cpu));
+        assert!(vip_cycles(0x4123, &cpu) > vip_cycles(0x4122, &cpu));
+    }
+
+    #[test]
+    fn should_charge_sprites_by_size_and_alignment() {
+        let mut cpu = CPU::new();
+        cpu.registers[0] = 8;
+        cpu.registers[1] = 0;
+
+        let aligned_small = vip_cycles(0xD011, &cpu);
+        let aligned_large = vip_cycles(0xD01F, &cpu);
+        cpu.registers[0] = 9;
+        let shifted_large = vip_cycles(0xD01F, &cpu);
+        cpu.registers[1] = 30;
+        let clipped_large = vip_cycles(0xD01F, &cpu);
+
+        assert!(aligned_small < aligned_large);
+        assert!(aligned_large < shifted_large);
+        assert!(clipped_large < shifted_large);
+    }
+
+    #[test]
+    fn should_charge_register_transfers_by_count() {
+        let cpu = CPU::new();
+
+        assert!(vip_cycles(0xF055, &cpu) < vip_cycles(0xFF55, &cpu));
+        assert!(vip_cycles(0xF365, &cpu) == vip_cycles(0xF355, &cpu));
+    }
+}
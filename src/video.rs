@@ -1,12 +1,20 @@
 pub mod video {
     use sdl2::rect::Rect; 
     use sdl2::rect::Point; 
-    use sdl2::pixels::Color; 
-    use sdl2::render::Canvas; 
-    use sdl2::video::Window; 
+    use sdl2::pixels::{Color, PixelFormatEnum}; 
+    use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator}; 
+    use sdl2::video::{Window, WindowContext}; 
     use sdl2::ttf::Font; 
+    use std::collections::HashMap; 
+    use std::fs::File; 
+    use std::io::BufWriter; 
+    use std::path::Path; 
 
-    use crate::cpu::cpu::{CPU, SCREEN_HEIGHT, SCREEN_WIDTH}; 
+    use crate::cpu::cpu::{CPU, FONT_HEIGHT, KeyQuery, SCREEN_HEIGHT, SCREEN_WIDTH}; 
+    use crate::debugger::debugger::{call_frames, decode_sprite, stack_overflow_risk, DebugPanel, DebugState, EditTarget, SpriteMode, MEMORY_BYTES_PER_ROW}; 
+    use crate::browser::browser::RomBrowser;
+    use crate::disasm::disasm::{disassemble, read_opcode};
+    use crate::postprocess::postprocess::{apply_crt, upscale_rgb_to_rgba, CrtSettings, BYTES_PER_RGBA_PIXEL}; 
 
     // -----------------
     // --- CONSTANTS ---
@@ -17,7 +25,6 @@ pub mod video {
     pub const CANVAS_HEIGHT      : usize = SCREEN_HEIGHT * PIXEL_WIDTH; 
     pub const DEBUG_CANVAS_WIDTH : usize = CANVAS_WIDTH * 2; 
     pub const DEBUG_CANVAS_HEIGHT: usize = CANVAS_HEIGHT * 2; 
-    const BACKGROUND_COLOR       : Color = Color::RGB(50, 50, 150);
     const REGION_WIDTH           : i32 = CANVAS_WIDTH as i32; 
     const REGION_HEIGHT          : i32 = CANVAS_HEIGHT as i32; 
     const ROM_REGION             : usize = 0; 
@@ -30,123 +37,695 @@ pub mod video {
         (0,            REGION_HEIGHT), 
         (REGION_WIDTH, REGION_HEIGHT)
     ];
+    const GLYPH_CHARS            : std::ops::RangeInclusive<char> = ' '..='~'; 
+    const MISSING_GLYPH          : char = '?'; 
+    const BYTES_PER_PIXEL        : usize = 3; 
+    const LETTERBOX_COLOR        : Color = Color::BLACK; 
+    const DEFAULT_DECAY          : f32 = 0.6; 
+    const REGISTER_COLUMNS       : i32 = 4; 
+    const MAX_SPRITE_CELL        : i32 = 12; 
+    const FONT_PREVIEW_CELL      : i32 = 4; 
+    const KEYPAD_KEY_SIZE        : i32 = 36; 
+    const KEYPAD_GAP             : i32 = 4; 
+    const KEYPAD_MARGIN          : i32 = 8; 
+    const KEYPAD_ALPHA           : u8 = 200; 
+    const KEYPAD_LAYOUT: [[usize; 4]; 4] = [   // COSMAC VIP keypad
+        [0x1, 0x2, 0x3, 0xC], 
+        [0x4, 0x5, 0x6, 0xD], 
+        [0x7, 0x8, 0x9, 0xE], 
+        [0xA, 0x0, 0xB, 0xF], 
+    ]; 
+    const BROWSER_MARGIN         : i32 = 12; 
+    const BROWSER_HEADER_ROWS    : i32 = 2;  // title and a blank line above the entries
+    const BROWSER_FOOTER_ROWS    : i32 = 2;  // key hints and errors below the entries
+
+    // ----------------
+    // --- PALETTES ---
+    // ----------------
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub struct Palette { 
+        pub name: &'static str, 
+        pub pixel_on: Color, 
+        pub pixel_off: Color, 
+        pub panel: Color,    // background behind the debug regions
+        pub text: Color, 
+        pub dim_text: Color, 
+        pub grid: Color,     // lines between debug regions
+        pub pc_highlight: Color, 
+        pub i_highlight: Color, 
+        pub changed: Color,  // memory written since the last frame
+        pub font: Color,     // font glyph bytes in the memory viewer
+    }
+
+    pub const PALETTES: [Palette; 5] = [
+        Palette { 
+            name: "classic", 
+            pixel_on: Color::RGB(255, 255, 255), 
+            pixel_off: Color::RGB(0, 0, 0), 
+            panel: Color::RGB(50, 50, 150), 
+            text: Color::RGB(255, 255, 255), 
+            dim_text: Color::RGB(128, 128, 128), 
+            grid: Color::RGB(255, 255, 255), 
+            pc_highlight: Color::RGB(255, 220, 0), 
+            i_highlight: Color::RGB(0, 230, 255), 
+            changed: Color::RGB(255, 80, 80), 
+            font: Color::RGB(140, 255, 140), 
+        }, 
+        Palette { 
+            name: "green", 
+            pixel_on: Color::RGB(51, 255, 102), 
+            pixel_off: Color::RGB(0, 26, 8), 
+            panel: Color::RGB(0, 40, 14), 
+            text: Color::RGB(51, 255, 102), 
+            dim_text: Color::RGB(24, 128, 52), 
+            grid: Color::RGB(51, 255, 102), 
+            pc_highlight: Color::RGB(255, 255, 255), 
+            i_highlight: Color::RGB(0, 200, 255), 
+            changed: Color::RGB(255, 90, 60), 
+            font: Color::RGB(230, 230, 120), 
+        }, 
+        Palette { 
+            name: "amber", 
+            pixel_on: Color::RGB(255, 176, 0), 
+            pixel_off: Color::RGB(26, 14, 0), 
+            panel: Color::RGB(46, 26, 0), 
+            text: Color::RGB(255, 176, 0), 
+            dim_text: Color::RGB(140, 96, 0), 
+            grid: Color::RGB(255, 176, 0), 
+            pc_highlight: Color::RGB(255, 255, 255), 
+            i_highlight: Color::RGB(255, 110, 0), 
+            changed: Color::RGB(255, 60, 40), 
+            font: Color::RGB(255, 230, 150), 
+        }, 
+        Palette { 
+            name: "lcd", 
+            pixel_on: Color::RGB(15, 56, 15), 
+            pixel_off: Color::RGB(155, 188, 15), 
+            panel: Color::RGB(139, 172, 15), 
+            text: Color::RGB(15, 56, 15), 
+            dim_text: Color::RGB(48, 98, 48), 
+            grid: Color::RGB(15, 56, 15), 
+            pc_highlight: Color::RGB(120, 20, 20), 
+            i_highlight: Color::RGB(20, 20, 140), 
+            changed: Color::RGB(160, 40, 120), 
+            font: Color::RGB(90, 70, 10), 
+        }, 
+        Palette { 
+            name: "high-contrast", 
+            pixel_on: Color::RGB(255, 255, 255), 
+            pixel_off: Color::RGB(0, 0, 0), 
+            panel: Color::RGB(0, 0, 0), 
+            text: Color::RGB(255, 255, 0), 
+            dim_text: Color::RGB(255, 255, 255), 
+            grid: Color::RGB(255, 255, 255), 
+            pc_highlight: Color::RGB(0, 255, 0), 
+            i_highlight: Color::RGB(0, 255, 255), 
+            changed: Color::RGB(255, 0, 255), 
+            font: Color::RGB(255, 128, 0), 
+        }, 
+    ]; 
+
+    impl Palette { 
+
+        // accepts a preset name or a custom "#RRGGBB,#RRGGBB" foreground/background pair
+        pub fn from_name(name: &str) -> Option<Palette> { 
+            if let Some(preset) = PALETTES.iter().find(|palette| palette.name == name) { 
+                return Some(*preset); 
+            }
+            let (on, off) = name.split_once(',')?; 
+            Some(Palette::custom(parse_hex_color(on)?, parse_hex_color(off)?))
+        }
+
+        // derive the remaining colors from a foreground and background
+        pub fn custom(pixel_on: Color, pixel_off: Color) -> Palette { 
+            Palette { 
+                name: "custom", 
+                pixel_on, 
+                pixel_off, 
+                panel: blend(pixel_off, pixel_on, 0.15), 
+                text: pixel_on, 
+                dim_text: blend(pixel_off, pixel_on, 0.5), 
+                grid: pixel_on, 
+                pc_highlight: PALETTES[0].pc_highlight, 
+                i_highlight: PALETTES[0].i_highlight, 
+                changed: PALETTES[0].changed, 
+                font: PALETTES[0].font, 
+            }
+        }
+
+        // next preset, custom palettes cycle back to the first preset
+        pub fn next(&self) -> Palette { 
+            match PALETTES.iter().position(|palette| palette.name == self.name) { 
+                Some(idx) => PALETTES[(idx + 1) % PALETTES.len()], 
+                None => PALETTES[0], 
+            }
+        }
+    }
+
+    pub fn parse_hex_color(value: &str) -> Option<Color> { 
+        let hex = value.trim().trim_start_matches('#'); 
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) { return None; }
+        let rgb = u32::from_str_radix(hex, 16).ok()?; 
+        Some(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+
+    fn blend(from: Color, to: Color, amount: f32) -> Color { 
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8; 
+        Color::RGB(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b))
+    }
+
+    // pixel intensities (0.0 = off, 1.0 = on) as packed RGB bytes, the single place
+    // pixels are mapped to colors so the window and screenshots always agree
+    pub fn frame_to_rgb(intensities: &[f32], palette: &Palette) -> Vec<u8> { 
+        let mut rgb: Vec<u8> = Vec::with_capacity(intensities.len() * BYTES_PER_PIXEL); 
+        for intensity in intensities.iter() { 
+            let color = blend(palette.pixel_off, palette.pixel_on, *intensity); 
+            rgb.extend_from_slice(&[color.r, color.g, color.b]); 
+        }
+        rgb
+    }
+
+    // the display upscaled by PIXEL_WIDTH to CANVAS_WIDTH x CANVAS_HEIGHT RGBA with
+    // the CRT stage applied, as shown in the window when the CRT filter is on
+    pub fn frame_to_canvas_rgba(intensities: &[f32], palette: &Palette, crt: &CrtSettings) -> Vec<u8> { 
+        let rgb = frame_to_rgb(intensities, palette); 
+        let mut rgba = upscale_rgb_to_rgba(&rgb, SCREEN_WIDTH, SCREEN_HEIGHT, PIXEL_WIDTH); 
+        apply_crt(&mut rgba, CANVAS_WIDTH, CANVAS_HEIGHT, PIXEL_WIDTH, crt); 
+        rgba
+    }
+
+    // write the display as a PNG, upscaled by PIXEL_WIDTH like the window
+    pub fn save_screenshot(path: &Path, intensities: &[f32], palette: &Palette, crt: &CrtSettings) -> Result<(), String> { 
+        let rgba = frame_to_canvas_rgba(intensities, palette, crt); 
+
+        let file = File::create(path).map_err(|e| e.to_string())?; 
+        let mut encoder = png::Encoder::new(BufWriter::new(file), CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32); 
+        encoder.set_color(png::ColorType::Rgba); 
+        encoder.set_depth(png::BitDepth::Eight); 
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?; 
+        writer.write_image_data(&rgba).map_err(|e| e.to_string())
+    }
+
+    // --------------------------
+    // --- SCALING / VIEWPORT ---
+    // --------------------------
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum ScaleMode { 
+        Integer, // every CHIP-8 pixel covers the same whole number of window pixels
+        Fit,     // stretch as far as the window allows while keeping the aspect ratio
+    }
+
+    impl ScaleMode { 
+        pub fn from_name(name: &str) -> Option<ScaleMode> { 
+            match name { 
+                "integer" => Some(ScaleMode::Integer), 
+                "fit" => Some(ScaleMode::Fit), 
+                _ => None, 
+            }
+        }
+
+        pub fn toggled(self) -> ScaleMode { 
+            match self { 
+                ScaleMode::Integer => ScaleMode::Fit, 
+                ScaleMode::Fit => ScaleMode::Integer, 
+            }
+        }
+    }
+
+    // where the logical canvas lands inside the window: x/y are window pixels and
+    // scale converts logical canvas pixels to window pixels
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub struct Viewport { 
+        pub x: i32, 
+        pub y: i32, 
+        pub scale: f32, 
+    }
+
+    impl Viewport { 
+        pub fn to_logical(self, window_x: i32, window_y: i32) -> (i32, i32) { 
+            (
+                ((window_x - self.x) as f32 / self.scale).floor() as i32, 
+                ((window_y - self.y) as f32 / self.scale).floor() as i32, 
+            )
+        }
+    }
+
+    // size of the canvas that all regions are laid out on
+    pub fn logical_size(debug: bool) -> (u32, u32) { 
+        match debug { 
+            true => (DEBUG_CANVAS_WIDTH as u32, DEBUG_CANVAS_HEIGHT as u32), 
+            false => (CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32), 
+        }
+    }
+
+    pub fn compute_viewport(window_size: (u32, u32), debug: bool, mode: ScaleMode) -> Viewport { 
+        let (window_width, window_height) = window_size; 
+        let (logical_width, logical_height) = logical_size(debug); 
+        let fit_scale = f32::min(
+            window_width as f32 / logical_width as f32, 
+            window_height as f32 / logical_height as f32
+        ); 
+
+        // integer mode snaps to whole window pixels per CHIP-8 pixel (never below one)
+        let scale = match mode { 
+            ScaleMode::Fit => fit_scale, 
+            ScaleMode::Integer => { 
+                let pixel_size = (fit_scale * PIXEL_WIDTH as f32).floor().max(1.0); 
+                pixel_size / PIXEL_WIDTH as f32
+            }
+        }; 
+
+        // center the canvas and letterbox the remaining space
+        Viewport { 
+            x: (window_width as i32 - (logical_width as f32 * scale) as i32) / 2, 
+            y: (window_height as i32 - (logical_height as f32 * scale) as i32) / 2, 
+            scale, 
+        }
+    }
+
+    // ---------------------------
+    // --- PERSISTENCE FILTERS ---
+    // ---------------------------
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum Persistence { 
+        Off, 
+        OrLastTwo,  // a pixel stays lit if it was on in this frame or the last one
+        Decay(f32), // unlit pixels fade by this factor per frame (0.0 - 1.0)
+    }
+
+    impl Persistence { 
+
+        // accepts off, or, decay or decay:{strength}
+        pub fn from_name(name: &str) -> Option<Persistence> { 
+            match name.split_once(':') { 
+                Some(("decay", strength)) => strength.parse::<f32>().ok()
+                    .filter(|strength| (0.0..1.0).contains(strength))
+                    .map(Persistence::Decay), 
+                Some(_) => None, 
+                None => match name { 
+                    "off" => Some(Persistence::Off), 
+                    "or" => Some(Persistence::OrLastTwo), 
+                    "decay" => Some(Persistence::Decay(DEFAULT_DECAY)), 
+                    _ => None, 
+                }
+            }
+        }
+
+        pub fn next(self) -> Persistence { 
+            match self { 
+                Persistence::Off => Persistence::OrLastTwo, 
+                Persistence::OrLastTwo => Persistence::Decay(DEFAULT_DECAY), 
+                Persistence::Decay(_) => Persistence::Off, 
+            }
+        }
+    }
+
+    // smooths XOR-redraw flicker by blending each pixel's recent on/off history
+    // into an intensity before the frame is colored
+    pub struct DisplayFilter { 
+        pub persistence: Persistence, 
+        previous: Vec<bool>, 
+        intensities: Vec<f32>, 
+    }
+
+    impl DisplayFilter { 
+
+        pub fn new(persistence: Persistence) -> Self { 
+            DisplayFilter { 
+                persistence, 
+                previous: vec![false; SCREEN_WIDTH * SCREEN_HEIGHT], 
+                intensities: vec![0.0; SCREEN_WIDTH * SCREEN_HEIGHT], 
+            }
+        }
+
+        // feed the next frame and return the filtered intensities
+        pub fn apply(&mut self, pixels: &[bool]) -> &[f32] { 
+            for (idx, pixel) in pixels.iter().enumerate() { 
+                let lit: f32 = if *pixel { 1.0 } else { 0.0 }; 
+                self.intensities[idx] = match self.persistence { 
+                    Persistence::Off => lit, 
+                    Persistence::OrLastTwo => if self.previous[idx] { 1.0 } else { lit }, 
+                    Persistence::Decay(strength) => lit.max(self.intensities[idx] * strength), 
+                }; 
+                self.previous[idx] = *pixel; 
+            }
+            &self.intensities
+        }
+
+        // forget the pixel history, e.g. after a new ROM was loaded
+        pub fn clear(&mut self) { 
+            self.previous.fill(false); 
+            self.intensities.fill(0.0); 
+        }
+
+        pub fn intensities(&self) -> &[f32] { 
+            &self.intensities
+        }
+    }
+
+    // --------------------------
+    // --- RENDERER RESOURCES ---
+    // --------------------------
+
+    struct Glyph<'a> { 
+        texture: Texture<'a>, 
+        width: u32, 
+        height: u32, 
+    }
+
+    // on-screen keypad drawn over the bottom right of the display
+    #[derive(Clone, Copy, Default, Debug)]
+    pub struct KeypadOverlay { 
+        pub visible: bool, 
+    }
+
+    // top left corner of the keypad overlay in logical canvas coordinates
+    fn keypad_origin() -> (i32, i32) { 
+        let size = KEYPAD_KEY_SIZE * 4 + KEYPAD_GAP * 3; 
+        (
+            REGIONS[ROM_REGION].0 + REGION_WIDTH - KEYPAD_MARGIN - size, 
+            REGIONS[ROM_REGION].1 + REGION_HEIGHT - KEYPAD_MARGIN - size, 
+        )
+    }
+
+    // find the keypad key under a point on the logical canvas
+    pub fn keypad_key_at(x: i32, y: i32) -> Option<usize> { 
+        let (origin_x, origin_y) = keypad_origin(); 
+        let pitch = KEYPAD_KEY_SIZE + KEYPAD_GAP; 
+        let (offset_x, offset_y) = (x - origin_x, y - origin_y); 
+        if offset_x < 0 || offset_y < 0 || offset_x % pitch >= KEYPAD_KEY_SIZE || offset_y % pitch >= KEYPAD_KEY_SIZE { 
+            return None; 
+        }
+        KEYPAD_LAYOUT.get((offset_y / pitch) as usize)?.get((offset_x / pitch) as usize).copied()
+    }
+
+    // textures that live for the whole session: the frame buffer is uploaded into a
+    // streaming texture once per frame and every printable glyph is rendered once
+    pub struct Renderer<'a> { 
+        screen_texture: Texture<'a>, 
+        crt_texture: Texture<'a>, 
+        glyphs: HashMap<char, Glyph<'a>>, 
+        line_height: i32, 
+        pub scale_mode: ScaleMode, 
+        pub palette: Palette, 
+        pub display_filter: DisplayFilter, 
+        pub crt: CrtSettings, 
+        pub keypad: KeypadOverlay, 
+    }
+
+    impl<'a> Renderer<'a> { 
+
+        pub fn new(texture_creator: &'a TextureCreator<WindowContext>, font: &Font, scale_mode: ScaleMode, palette: Palette, persistence: Persistence, crt: CrtSettings) -> Result<Self, String> { 
+            let screen_texture = texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
+                .map_err(|e| e.to_string())?; 
+
+            // post-processed frames are uploaded at full canvas resolution
+            let crt_texture = texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGBA32, CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32)
+                .map_err(|e| e.to_string())?; 
+
+            // glyphs are rendered white so they can be tinted with a color mod
+            let mut glyphs: HashMap<char, Glyph<'a>> = HashMap::new(); 
+            for ch in GLYPH_CHARS { 
+                let surface = font.render_char(ch)
+                    .blended(Color::WHITE)
+                    .map_err(|e| e.to_string())?; 
+                let texture = texture_creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|e| e.to_string())?; 
+                glyphs.insert(ch, Glyph { texture, width: surface.width(), height: surface.height() }); 
+            }
+
+            Ok(Renderer { 
+                screen_texture, 
+                crt_texture, 
+                glyphs, 
+                line_height: font.height(), 
+                scale_mode, 
+                palette, 
+                display_filter: DisplayFilter::new(persistence), 
+                crt, 
+                keypad: KeypadOverlay::default(), 
+            })
+        }
+
+        pub fn viewport(&self, canvas: &Canvas<Window>, debug: bool) -> Viewport { 
+            let window_size = canvas.output_size().expect("ERROR:: failed to get window size"); 
+            compute_viewport(window_size, debug, self.scale_mode)
+        }
+
+        pub fn line_height(&self) -> i32 { 
+            self.line_height
+        }
+
+        // advance of one character, the debug font is monospaced
+        pub fn char_width(&self) -> i32 { 
+            self.text_width("0") as i32
+        }
+
+        fn text_width(&self, text: &str) -> u32 { 
+            text.chars()
+                .filter_map(|ch| self.glyphs.get(&ch).or_else(|| self.glyphs.get(&MISSING_GLYPH)))
+                .map(|glyph| glyph.width)
+                .sum()
+        }
+    }
+
+    // copy tightly packed rows into a streaming texture that may have a wider pitch
+    fn upload_rows(texture: &mut Texture, bytes: &[u8], row_len: usize) { 
+        texture.with_lock(None, |buffer: &mut [u8], pitch: usize| { 
+            for (row, row_bytes) in bytes.chunks(row_len).enumerate() { 
+                buffer[row * pitch..row * pitch + row_len].copy_from_slice(row_bytes); 
+            }
+        }).expect("ERROR:: failed to lock screen texture"); 
+    }
 
     // ------------------------
     // --- PUBLIC FUNCTIONS ---
     // ------------------------
 
-    pub fn write_text(text: String, x_off: i32, y_off: i32, color: Color, font: &Font, canvas: &mut Canvas<Window>) { 
-        // text surface 
-        let surface = 
-            font.render(&text)
-                .blended(color)
-                .expect("ERROR:: failed to render text"); 
+    pub fn write_text(text: String, x_off: i32, y_off: i32, color: Color, renderer: &mut Renderer, canvas: &mut Canvas<Window>) { 
+        // text is centered horizontally on x_off
+        let mut x = x_off - ((renderer.text_width(&text) / 2) as i32);
 
-        // text texture 
-        let texture_creator = canvas.texture_creator();
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .expect("ERROR: failed to create text texture");
-        let (width, height) = surface.size(); 
+        // copy cached glyphs to canvas one character at a time
+        for ch in text.chars() { 
+            let key = if renderer.glyphs.contains_key(&ch) { ch } else { MISSING_GLYPH }; 
+            let glyph = renderer.glyphs.get_mut(&key).expect("ERROR:: missing fallback glyph"); 
+            glyph.texture.set_color_mod(color.r, color.g, color.b); 
+            glyph.texture.set_alpha_mod(color.a); 
+            canvas.copy(&glyph.texture, None, Rect::new(x, y_off, glyph.width, glyph.height))
+                .expect("ERROR:: failed to copy text to canvas");
+            x += glyph.width as i32; 
+        }
+    }
 
-        // copy text to canvas
-        let x = x_off - ((width/ 2) as i32);
-        canvas.copy(&texture, None, Rect::new(x, y_off, width, height))
-            .expect("ERROR:: failed to copy text to canvas");
+    // like write_text but anchored at the left edge instead of the center
+    pub fn write_text_left(text: String, x_off: i32, y_off: i32, color: Color, renderer: &mut Renderer, canvas: &mut Canvas<Window>) { 
+        let width = renderer.text_width(&text) as i32; 
+        write_text(text, x_off + width / 2, y_off, color, renderer, canvas); 
     }
 
-    pub fn draw_rom_region(canvas: &mut Canvas<Window>, pixels: &[bool; 2048]) { 
+    // draws the intensities of the last filtered frame, redraws between frames do not advance the filter
+    pub fn draw_rom_region(canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
         let (region_x, region_y) = REGIONS[ROM_REGION]; 
-        for (idx, pixel) in pixels.into_iter().enumerate() { 
-            let row: usize = idx / SCREEN_WIDTH; 
-            let col: usize = idx % SCREEN_WIDTH;
-            let rect = Rect::new(
-                region_x + (col * PIXEL_WIDTH) as i32, 
-                region_y + (row * PIXEL_WIDTH) as i32, 
-                PIXEL_WIDTH as u32, 
-                PIXEL_WIDTH as u32
-            );
-            let color: Color = if *pixel { 
-                Color::WHITE
-            } else { 
-                Color::BLACK
-            };
-            
-            canvas.set_draw_color(color);
-            canvas.fill_rect(rect).expect("rect not filled correctly when drawing screen!!");
+
+        let intensities = renderer.display_filter.intensities(); 
+        let rect = Rect::new(region_x, region_y, CANVAS_WIDTH as u32, CANVAS_HEIGHT as u32); 
+
+        // post-process on the CPU at canvas resolution when the CRT filter is on
+        if renderer.crt.enabled { 
+            let rgba = frame_to_canvas_rgba(intensities, &renderer.palette, &renderer.crt); 
+            upload_rows(&mut renderer.crt_texture, &rgba, CANVAS_WIDTH * BYTES_PER_RGBA_PIXEL); 
+            canvas.copy(&renderer.crt_texture, None, rect)
+                .expect("ERROR:: failed to copy screen texture to canvas");
+            return; 
+        }
+
+        // otherwise upload frame buffer as is and let the renderer scale the texture up to the region
+        let rgb = frame_to_rgb(intensities, &renderer.palette); 
+        upload_rows(&mut renderer.screen_texture, &rgb, SCREEN_WIDTH * BYTES_PER_PIXEL); 
+        canvas.copy(&renderer.screen_texture, None, rect)
+            .expect("ERROR:: failed to copy screen texture to canvas");
+    }
+
+    // column (1 based) and row of each editable value in the register region
+    fn register_cell(target: EditTarget) -> Option<(i32, i32)> { 
+        match target { 
+            EditTarget::Register(idx) => Some((idx as i32 % REGISTER_COLUMNS + 1, idx as i32 / REGISTER_COLUMNS + 1)), 
+            EditTarget::I => Some((1, 5)), 
+            EditTarget::Pc => Some((2, 5)), 
+            EditTarget::Sp => Some((3, 5)), 
+            EditTarget::DelayTimer => Some((1, 6)), 
+            EditTarget::SoundTimer => Some((2, 6)), 
+            EditTarget::Memory(_) => None, 
         }
     }
 
-    pub fn draw_register_region(cpu: &CPU, canvas: &mut Canvas<Window>, font: &Font) { 
+    fn register_targets() -> Vec<EditTarget> { 
+        let mut targets: Vec<EditTarget> = (0..16).map(EditTarget::Register).collect(); 
+        targets.extend([EditTarget::I, EditTarget::Pc, EditTarget::Sp, EditTarget::DelayTimer, EditTarget::SoundTimer]); 
+        targets
+    }
+
+    // x of the first memory byte column, shared by drawing and hit testing
+    fn memory_bytes_x(char_width: i32) -> i32 { 
+        REGIONS[I_REGION].0 + char_width * 10
+    }
+
+    // find the editable value under a point on the logical debug canvas
+    pub fn edit_target_at(x: i32, y: i32, line_height: i32, char_width: i32, debug_state: &DebugState, memory_len: usize) -> Option<EditTarget> { 
         let (region_x, region_y) = REGIONS[REGISTERS_REGION]; 
-        let row_height = 2*font.height(); 
-        let columns = 4; 
+        let row_height = 2*line_height; 
+        let cell_width = REGION_WIDTH / (REGISTER_COLUMNS + 1); 
+        for target in register_targets() { 
+            let Some((col, row)) = register_cell(target) else { continue }; 
+            let center_x = region_x + cell_width * col; 
+            let top_y = region_y + row_height * row; 
+            if (x - center_x).abs() < cell_width / 2 && (top_y..top_y + line_height).contains(&y) { 
+                return Some(target); 
+            }
+        }
+
+        // memory bytes are only clickable while the memory panel is shown
+        let (region_x, region_y) = REGIONS[I_REGION]; 
+        if debug_state.panel != DebugPanel::Memory 
+            || x < region_x || y < region_y + line_height || y >= region_y + REGION_HEIGHT { 
+            return None; 
+        }
+        let row = ((y - region_y) / line_height - 1) as usize; 
+        let col_offset = x - memory_bytes_x(char_width); 
+        if col_offset < 0 || col_offset % (char_width * 3) >= char_width * 2 { 
+            return None; 
+        }
+        let col = (col_offset / (char_width * 3)) as usize; 
+        let addr = (debug_state.memory_scroll + row) * MEMORY_BYTES_PER_ROW + col; 
+        (col < MEMORY_BYTES_PER_ROW && addr < memory_len).then_some(EditTarget::Memory(addr))
+    }
+
+    pub fn draw_register_region(cpu: &CPU, debug_state: &DebugState, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
+        let (region_x, region_y) = REGIONS[REGISTERS_REGION]; 
+        let row_height = 2*renderer.line_height(); 
+        let text_color = renderer.palette.text; 
         let title_row = 0; 
-        let v_register_row = 1; 
-        let i_register_row = 5; 
-        let pc_row = 5;
 
         // write title for this region
         write_text("--- REGISTERS ---".to_string(), 
         region_x + (REGION_WIDTH / 2), 
         region_y + row_height * title_row, 
-            Color::WHITE, 
-            font, 
+            text_color, 
+            renderer, 
             canvas);
 
-        // write grid of registers and their values
-        for (idx, value) in cpu.registers.iter().enumerate() { 
-            let x_off = region_x + (REGION_WIDTH / (columns + 1)) * ((idx as i32 % columns) + 1); 
-            let y_off = region_y + (row_height * v_register_row) + (row_height * (idx as i32 / columns));
-            write_text(
-                format!("V{:X}: {:#04x}", idx, value), 
-                x_off, 
-                y_off,
-                Color::WHITE, 
-                font, 
-                canvas); 
+        // write grid of registers and their values, the one being edited shows the typed input
+        for target in register_targets() { 
+            let (col, row) = register_cell(target).expect("ERROR:: register without a cell"); 
+            let x_off = region_x + (REGION_WIDTH / (REGISTER_COLUMNS + 1)) * col; 
+            let y_off = region_y + row_height * row; 
+            let (text, color) = match &debug_state.edit { 
+                Some(edit) if edit.target == target => (format!("{}: {}_", target.label(), edit.input), renderer.palette.pc_highlight), 
+                _ => (format_register(target, cpu), text_color), 
+            }; 
+            write_text(text, x_off, y_off, color, renderer, canvas); 
         }
+    }
 
-        // write I register value
-        {
-            let x_off = region_x + REGION_WIDTH / (columns + 1); 
-            let y_off = region_y + row_height * i_register_row; 
-            write_text(
-                format!("I : x{:03x}", cpu.reg_i), 
-                x_off, 
-                y_off,
-                Color::WHITE, 
-                font, 
-                canvas); 
+    fn format_register(target: EditTarget, cpu: &CPU) -> String { 
+        let value = target.read(cpu); 
+        match target { 
+            EditTarget::I | EditTarget::Pc => format!("{}: x{:03x}", target.label(), value), 
+            _ => format!("{}: {:#04x}", target.label(), value), 
         }
+    }
 
-        // write PC value
-        {
-            let x_off = region_x + REGION_WIDTH / (columns + 1) * 2; 
-            let y_off = region_y + row_height * pc_row; 
-            write_text(
-                format!("PC: x{:03x}", cpu.pc), 
-                x_off, 
-                y_off,
-                Color::WHITE, 
-                font, 
-                canvas); 
+    // number of instruction rows in the disassembly listing
+    fn disasm_rows(line_height: i32) -> usize { 
+        (REGION_HEIGHT / line_height - 1) as usize
+    }
+
+    // address shown on a row of the disassembly listing, rows are two bytes apart
+    // with the center address in the middle row
+    fn disasm_row_addr(row: usize, rows: usize, center: usize, memory_len: usize) -> Option<usize> { 
+        let addr = (center + row * 2).checked_sub((rows / 2) * 2)?; 
+        (addr < memory_len).then_some(addr)
+    }
+
+    // find the disassembly row under a point on the logical debug canvas
+    pub fn disasm_addr_at(x: i32, y: i32, line_height: i32, center: usize, memory_len: usize) -> Option<usize> { 
+        let (region_x, region_y) = REGIONS[PC_REGION]; 
+        if x < region_x || x >= region_x + REGION_WIDTH || y < region_y + line_height || y >= region_y + REGION_HEIGHT { 
+            return None; 
         }
+        let row = ((y - region_y) / line_height - 1) as usize; 
+        disasm_row_addr(row, disasm_rows(line_height), center, memory_len)
     }
 
-    pub fn draw_pc_region(cpu: &CPU, canvas: &mut Canvas<Window>, font: &Font) { 
+    pub fn draw_pc_region(cpu: &CPU, debug_state: &DebugState, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
         let (region_x, region_y) = REGIONS[PC_REGION]; 
-        let row_height = 2*font.height(); 
+        let row_height = renderer.line_height(); 
+        let char_width = renderer.char_width(); 
+        let text_color = renderer.palette.text; 
+        let dim_color = renderer.palette.dim_text; 
+        let rows = disasm_rows(row_height); 
+        let center = debug_state.disasm_center(cpu.pc); 
+
+        // write title for this region, noting when the listing no longer follows the PC
+        let title = match debug_state.disasm_center { 
+            Some(_) => "--- DISASSEMBLY [HOME] FOLLOW PC ---", 
+            None => "--- DISASSEMBLY ---", 
+        }; 
+        write_text(title.to_string(), region_x + (REGION_WIDTH / 2), region_y, text_color, renderer, canvas); 
+
+        // rows of "* > addr opcode  mnemonic", '*' marks a breakpoint and '>' the PC
+        let marker_x = region_x + char_width * 2; 
+        let address_x = marker_x + char_width * 4; 
+        let opcode_x = address_x + char_width * 5; 
+        let mnemonic_x = opcode_x + char_width * 6; 
+        for row in 0..rows { 
+            let addr = match disasm_row_addr(row, rows, center, cpu.memory.len()) { 
+                Some(addr) => addr, 
+                None => continue, 
+            }; 
+            let y_off = region_y + row_height * (row as i32 + 1); 
+            let opcode = read_opcode(&cpu.memory, addr); 
+            let is_current = addr == cpu.pc; 
+
+            if debug_state.breakpoints.contains(&addr) { 
+                write_text_left("*".to_string(), marker_x, y_off, renderer.palette.changed, renderer, canvas); 
+            }
+            if is_current { 
+                write_text_left(">".to_string(), marker_x + char_width * 2, y_off, renderer.palette.pc_highlight, renderer, canvas); 
+            }
+
+            let color = if is_current { renderer.palette.pc_highlight } else { text_color }; 
+            let mnemonic = match debug_state.symbols.label(addr) { 
+                Some(label) => format!("{}: {}", label, disassemble(opcode, &debug_state.symbols)), 
+                None => disassemble(opcode, &debug_state.symbols), 
+            }; 
+            write_text_left(format!("x{:03x}", addr), address_x, y_off, dim_color, renderer, canvas); 
+            write_text_left(format!("{:04x}", opcode), opcode_x, y_off, dim_color, renderer, canvas); 
+            write_text_left(mnemonic, mnemonic_x, y_off, color, renderer, canvas); 
+        }
+    } 
+
+    pub fn draw_i_region(cpu: &CPU, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
+        let (region_x, region_y) = REGIONS[I_REGION]; 
+        let row_height = 2*renderer.line_height(); 
+        let text_color = renderer.palette.text; 
         let columns = 4; 
         let title_row = 0; 
         let memory_row = 1; 
 
         // write title for this region
-        write_text("--- PC POINTER ---".to_string(), 
+        write_text("--- I REGISTER POINTER ---".to_string(), 
         region_x + (REGION_WIDTH / 2), 
         region_y + row_height * title_row, 
-            Color::WHITE, 
-            font, 
+            text_color, 
+            renderer, 
             canvas);
 
         // address and memory header
@@ -157,8 +736,8 @@ pub mod video {
                 "ADDR".to_string(), 
                 x_off, 
                 y_off,
-                Color::WHITE, 
-                font, 
+                text_color, 
+                renderer, 
                 canvas); 
         }
 
@@ -169,121 +748,344 @@ pub mod video {
                 "MEM_".to_string(), 
                 x_off, 
                 y_off,
-                Color::WHITE, 
-                font, 
+                text_color, 
+                renderer, 
                 canvas); 
         }
 
         // draw pc and it's matching memory
-        for i in (0..10).step_by(2) { 
-            if cpu.pc + (i*2) >= 0x1000 { continue; }
+        for (row, (addr, word)) in i_region_words(cpu).into_iter().enumerate() { 
             {
                 let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 2; 
-                let y_off = region_y + (row_height * (memory_row + 1 + (i/2) as i32));
+                let y_off = region_y + (row_height * (memory_row + 1 + row as i32));
                 write_text(
-                    format!("{:#04x}", cpu.pc + i), 
+                    format!("{:#04x}", addr), 
                     x_off, 
                     y_off,
-                    Color::WHITE, 
-                    font, 
+                    text_color, 
+                    renderer, 
                     canvas); 
             }
             {
                 let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 3; 
-                let y_off = region_y + (row_height * (memory_row + 1 + (i/2) as i32));
+                let y_off = region_y + (row_height * (memory_row + 1 + row as i32));
                 write_text(
-                    format!("{:02x}{:02x}", cpu.memory[cpu.pc+i], cpu.memory[cpu.pc+i+1]), 
+                    word, 
                     x_off, 
                     y_off,
-                    Color::WHITE, 
-                    font, 
+                    text_color, 
+                    renderer, 
                     canvas); 
             }
         }
-    } 
+    }
 
-    pub fn draw_i_region(cpu: &CPU, canvas: &mut Canvas<Window>, font: &Font) { 
+    // addresses and words shown from I onwards, a byte past the end of memory shows as --
+    pub fn i_region_words(cpu: &CPU) -> Vec<(usize, String)> { 
+        let byte = |addr: usize| cpu.memory.get(addr).map(|value| format!("{:02x}", value)).unwrap_or("--".to_string()); 
+        (0..10).step_by(2)
+            .map(|offset| cpu.reg_i + offset)
+            .filter(|addr| *addr < cpu.memory.len())
+            .map(|addr| (addr, byte(addr) + &byte(addr + 1)))
+            .collect()
+    }
+
+    pub fn draw_memory_region(cpu: &CPU, debug_state: &DebugState, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
         let (region_x, region_y) = REGIONS[I_REGION]; 
-        let row_height = 2*font.height(); 
-        let columns = 4; 
-        let title_row = 0; 
-        let memory_row = 1; 
+        let row_height = renderer.line_height(); 
+        let char_width = renderer.char_width(); 
+        let text_color = renderer.palette.text; 
+        let dim_color = renderer.palette.dim_text; 
+        let visible_rows = (REGION_HEIGHT / row_height - 1) as usize; 
+        let rom_range = cpu.rom_start()..cpu.rom_start() + cpu.rom_len(); 
+        let font_range = cpu.font_range(); 
 
-        // write title for this region
-        write_text("--- I REGISTER POINTER ---".to_string(), 
-        region_x + (REGION_WIDTH / 2), 
-        region_y + row_height * title_row, 
-            Color::WHITE, 
-            font, 
-            canvas);
+        // write title for this region, showing the goto prompt while typing
+        let title = match &debug_state.goto_input { 
+            Some(input) => format!("--- MEMORY  GOTO: {}_ ---", input), 
+            None => "--- MEMORY [G]OTO ---".to_string(), 
+        }; 
+        write_text(title, region_x + (REGION_WIDTH / 2), region_y, text_color, renderer, canvas); 
 
-        // address and memory header
-        { 
-            let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 2; 
-            let y_off = region_y + (row_height * memory_row);
-            write_text(
-                "ADDR".to_string(), 
-                x_off, 
-                y_off,
-                Color::WHITE, 
-                font, 
-                canvas); 
+        // rows of "addr: bytes", left aligned so the columns line up
+        let address_x = region_x + char_width * 3; 
+        let bytes_x = memory_bytes_x(char_width); 
+        for row in 0..visible_rows { 
+            let row_addr = (debug_state.memory_scroll + row) * MEMORY_BYTES_PER_ROW; 
+            if row_addr >= cpu.memory.len() { break; }
+            let y_off = region_y + row_height * (row as i32 + 1); 
+            write_text_left(format!("{:03x}:", row_addr), address_x, y_off, dim_color, renderer, canvas); 
+
+            for col in 0..MEMORY_BYTES_PER_ROW { 
+                let addr = row_addr + col; 
+                let color = if addr == cpu.pc || addr == cpu.pc + 1 { 
+                    renderer.palette.pc_highlight
+                } else if addr == cpu.reg_i { 
+                    renderer.palette.i_highlight
+                } else if debug_state.is_changed(addr) { 
+                    renderer.palette.changed
+                } else if font_range.contains(&addr) { 
+                    renderer.palette.font
+                } else if rom_range.contains(&addr) { 
+                    text_color
+                } else { 
+                    dim_color
+                }; 
+                let x_off = bytes_x + char_width * 3 * col as i32; 
+                match &debug_state.edit { 
+                    Some(edit) if edit.target == EditTarget::Memory(addr) => { 
+                        let text = format!("{:_<2}", edit.input); 
+                        write_text_left(text, x_off, y_off, renderer.palette.pc_highlight, renderer, canvas); 
+                    }, 
+                    _ => write_text_left(format!("{:02x}", cpu.memory[addr]), x_off, y_off, color, renderer, canvas), 
+                }
+            }
+        }
+    }
+
+    pub fn draw_call_stack_region(cpu: &CPU, debug_state: &DebugState, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
+        let (region_x, region_y) = REGIONS[I_REGION]; 
+        let row_height = renderer.line_height(); 
+        let char_width = renderer.char_width(); 
+        let text_color = renderer.palette.text; 
+        let dim_color = renderer.palette.dim_text; 
+        let frames = call_frames(cpu); 
+
+        // write title with the stack depth and its high-water mark, turning to a warning when close to overflowing
+        let (title, title_color) = match stack_overflow_risk(cpu) { 
+            true => (format!("--- CALL STACK {}/{} PEAK {} OVERFLOW RISK ---", cpu.sp, cpu.stack.len(), cpu.stack_high_water()), renderer.palette.changed), 
+            false => (format!("--- CALL STACK {}/{} PEAK {} ---", cpu.sp, cpu.stack.len(), cpu.stack_high_water()), text_color), 
+        }; 
+        write_text(title, region_x + (REGION_WIDTH / 2), region_y, title_color, renderer, canvas); 
+
+        if frames.is_empty() { 
+            write_text("(no active calls)".to_string(), region_x + (REGION_WIDTH / 2), region_y + row_height * 2, dim_color, renderer, canvas); 
+            return; 
         }
 
-        { 
-            let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 3; 
-            let y_off = region_y + (row_height * memory_row);
-            write_text(
-                "MEM_".to_string(), 
-                x_off, 
-                y_off,
-                Color::WHITE, 
-                font, 
-                canvas); 
+        // one row per frame, innermost first: "#depth entry label   ret address label"
+        let label_of = |addr: usize| debug_state.symbols.describe(addr).unwrap_or_default(); 
+        let depth_x = region_x + char_width * 3; 
+        let entry_x = depth_x + char_width * 4; 
+        let return_x = entry_x + char_width * 22; 
+        for (row, frame) in frames.iter().enumerate() { 
+            let y_off = region_y + row_height * (row as i32 + 1); 
+            if y_off + row_height > region_y + REGION_HEIGHT { break; }
+            let entry_color = if row == 0 { renderer.palette.pc_highlight } else { text_color }; 
+            write_text_left(format!("#{}", frames.len() - row), depth_x, y_off, dim_color, renderer, canvas); 
+            write_text_left(format!("x{:03x} {}", frame.entry, label_of(frame.entry)), entry_x, y_off, entry_color, renderer, canvas); 
+            write_text_left(format!("ret x{:03x} {}", frame.return_addr, label_of(frame.return_addr)), return_x, y_off, dim_color, renderer, canvas); 
         }
+    }
 
-        // draw pc and it's matching memory
-        for i in (0..10).step_by(2) { 
-            if cpu.reg_i + (i*2) >= 0x1000 { continue; }
-            {
-                let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 2; 
-                let y_off = region_y + (row_height * (memory_row + 1 + (i/2) as i32));
-                write_text(
-                    format!("{:#04x}", cpu.reg_i + i), 
-                    x_off, 
-                    y_off,
-                    Color::WHITE, 
-                    font, 
-                    canvas); 
+    // draw a bitmap with every pixel as a cell x cell square in the display colors
+    fn draw_bitmap(rows: &[Vec<bool>], x_off: i32, y_off: i32, cell: i32, on_color: Color, canvas: &mut Canvas<Window>, renderer: &Renderer) { 
+        let width = rows.first().map(|row| row.len()).unwrap_or(0) as u32; 
+        canvas.set_draw_color(renderer.palette.pixel_off); 
+        canvas.fill_rect(Rect::new(x_off, y_off, width * cell as u32, rows.len() as u32 * cell as u32)).unwrap(); 
+
+        canvas.set_draw_color(on_color); 
+        for (row_idx, row) in rows.iter().enumerate() { 
+            for (col_idx, lit) in row.iter().enumerate() { 
+                if !lit { continue; }
+                let rect = Rect::new(x_off + col_idx as i32 * cell, y_off + row_idx as i32 * cell, cell as u32, cell as u32); 
+                canvas.fill_rect(rect).unwrap(); 
             }
-            {
-                let x_off = region_x + (REGION_WIDTH / (columns + 1)) * 3; 
-                let y_off = region_y + (row_height * (memory_row + 1 + (i/2) as i32));
-                write_text(
-                    format!("{:02x}{:02x}", cpu.memory[cpu.reg_i+i], cpu.memory[cpu.reg_i+i+1]), 
-                    x_off, 
-                    y_off,
-                    Color::WHITE, 
-                    font, 
-                    canvas); 
+        }
+    }
+
+    pub fn draw_sprite_region(cpu: &CPU, debug_state: &DebugState, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
+        let (region_x, region_y) = REGIONS[I_REGION]; 
+        let row_height = renderer.line_height(); 
+        let char_width = renderer.char_width(); 
+        let text_color = renderer.palette.text; 
+        let dim_color = renderer.palette.dim_text; 
+        let addr = debug_state.sprite_addr.unwrap_or(cpu.reg_i); 
+
+        // write title for this region, showing the goto prompt while typing
+        let title = match (&debug_state.goto_input, debug_state.sprite_addr) { 
+            (Some(input), _) => format!("--- SPRITE  GOTO: {}_ ---", input), 
+            (None, Some(_)) => format!("--- SPRITE x{:03x} ---", addr), 
+            (None, None) => format!("--- SPRITE AT I x{:03x} ---", addr), 
+        }; 
+        write_text(title, region_x + (REGION_WIDTH / 2), region_y, text_color, renderer, canvas); 
+
+        // sprite bitmap scaled to fit above the font preview
+        let font_title_y = region_y + REGION_HEIGHT - row_height * 3 - FONT_PREVIEW_CELL * FONT_HEIGHT as i32; 
+        let sprite_y = region_y + row_height * 2; 
+        let rows = decode_sprite(&cpu.memory, addr, debug_state.sprite_mode, debug_state.sprite_height); 
+        let cell = ((font_title_y - sprite_y - row_height) / rows.len() as i32).min(MAX_SPRITE_CELL); 
+        let sprite_x = region_x + char_width * 3; 
+        draw_bitmap(&rows, sprite_x, sprite_y, cell, renderer.palette.pixel_on, canvas, renderer); 
+
+        // sprite details and controls next to the bitmap
+        let (size, bytes) = match debug_state.sprite_mode { 
+            SpriteMode::Narrow => (format!("8x{}", rows.len()), rows.len()), 
+            SpriteMode::Large => ("16x16".to_string(), 32), 
+        }; 
+        let info_x = sprite_x + cell * 16 + char_width * 3; 
+        let info = [ 
+            (format!("SIZE {}", size), text_color), 
+            (format!("x{:03x} - x{:03x}", addr, (addr + bytes - 1) % cpu.memory.len()), text_color), 
+            ("[L] 8xN / 16x16".to_string(), dim_color), 
+            ("[-] [=] HEIGHT".to_string(), dim_color), 
+            ("[G]OTO  [I] FOLLOW I".to_string(), dim_color), 
+        ]; 
+        for (line, (text, color)) in info.into_iter().enumerate() { 
+            write_text_left(text, info_x, sprite_y + row_height * line as i32, color, renderer, canvas); 
+        }
+
+        // font preview read back from memory, glyphs that differ from the loaded font are flagged
+        let font_start = cpu.font_range().start; 
+        write_text(format!("--- FONT x{:03x} ---", font_start), region_x + (REGION_WIDTH / 2), font_title_y, text_color, renderer, canvas); 
+        let glyph_pitch = (REGION_WIDTH - char_width * 4) / cpu.font().len() as i32; 
+        let glyph_y = font_title_y + row_height + row_height / 2; 
+        for (digit, expected) in cpu.font().iter().enumerate() { 
+            let glyph_addr = font_start + digit * FONT_HEIGHT; 
+            let loaded = &cpu.memory[glyph_addr..glyph_addr + FONT_HEIGHT]; 
+            let color = if loaded == expected { renderer.palette.pixel_on } else { renderer.palette.changed }; 
+            let glyph_x = region_x + char_width * 2 + glyph_pitch * digit as i32; 
+            let glyph = decode_sprite(&cpu.memory, glyph_addr, SpriteMode::Narrow, FONT_HEIGHT); 
+            draw_bitmap(&glyph, glyph_x, glyph_y, FONT_PREVIEW_CELL, color, canvas, renderer); 
+
+            let label_color = if loaded == expected { dim_color } else { renderer.palette.changed }; 
+            write_text(format!("{:X}", digit), glyph_x + FONT_PREVIEW_CELL * 2, glyph_y + FONT_PREVIEW_CELL * FONT_HEIGHT as i32, label_color, renderer, canvas); 
+        }
+    }
+
+    // keys held down are filled, keys the program last tested are outlined
+    pub fn draw_keypad(cpu: &CPU, canvas: &mut Canvas<Window>, renderer: &mut Renderer) { 
+        let (origin_x, origin_y) = keypad_origin(); 
+        let pitch = KEYPAD_KEY_SIZE + KEYPAD_GAP; 
+        let panel = renderer.palette.panel; 
+
+        for (row, keys) in KEYPAD_LAYOUT.iter().enumerate() { 
+            for (col, key) in keys.iter().enumerate() { 
+                let rect = Rect::new(origin_x + pitch * col as i32, origin_y + pitch * row as i32, KEYPAD_KEY_SIZE as u32, KEYPAD_KEY_SIZE as u32); 
+                let pressed = cpu.is_key_pressed(*key); 
+
+                canvas.set_blend_mode(BlendMode::Blend); 
+                canvas.set_draw_color(match pressed { 
+                    true => renderer.palette.pixel_on, 
+                    false => Color::RGBA(panel.r, panel.g, panel.b, KEYPAD_ALPHA), 
+                }); 
+                canvas.fill_rect(rect).unwrap(); 
+                canvas.set_blend_mode(BlendMode::None); 
+
+                let outline = match cpu.last_key_query { 
+                    Some(KeyQuery::Key(tested)) if tested == *key => Some(renderer.palette.pc_highlight), 
+                    Some(KeyQuery::AnyKey) => Some(renderer.palette.i_highlight), 
+                    _ => None, 
+                }; 
+                if let Some(color) = outline { 
+                    canvas.set_draw_color(color); 
+                    canvas.draw_rect(rect).unwrap(); 
+                }
+
+                let text_color = if pressed { renderer.palette.pixel_off } else { renderer.palette.text }; 
+                let text_y = rect.y() + (KEYPAD_KEY_SIZE - renderer.line_height()) / 2; 
+                write_text(format!("{:X}", key), rect.x() + KEYPAD_KEY_SIZE / 2, text_y, text_color, renderer, canvas); 
             }
         }
     }
 
-    pub fn draw_entire_window(canvas: &mut Canvas<Window>, cpu: &CPU, font: &Font, debug: bool, paused_state: bool) { 
-    canvas.set_draw_color(BACKGROUND_COLOR); 
-    canvas.clear();
+    // number of entries the rom browser shows at once
+    pub fn browser_rows(line_height: i32, debug: bool) -> usize { 
+        let (_, logical_height) = logical_size(debug); 
+        let rows = (logical_height as i32 - BROWSER_MARGIN * 2) / line_height - BROWSER_HEADER_ROWS - BROWSER_FOOTER_ROWS; 
+        rows.max(1) as usize
+    }
+
+    // index of the browser entry under a point in logical coordinates
+    pub fn browser_entry_at(x: i32, y: i32, line_height: i32, debug: bool, browser: &RomBrowser) -> Option<usize> { 
+        let (logical_width, _) = logical_size(debug); 
+        let list_y = BROWSER_MARGIN + line_height * BROWSER_HEADER_ROWS; 
+        if x < BROWSER_MARGIN || x >= logical_width as i32 - BROWSER_MARGIN || y < list_y { 
+            return None; 
+        }
+        let row = ((y - list_y) / line_height) as usize; 
+        let idx = browser.scroll + row; 
+        (row < browser_rows(line_height, debug) && idx < browser.entries.len()).then_some(idx)
+    }
+
+    // directory listing shown when no rom was given on the command line
+    pub fn draw_rom_browser(browser: &RomBrowser, canvas: &mut Canvas<Window>, renderer: &mut Renderer, debug: bool) { 
+        let (logical_width, logical_height) = begin_frame(canvas, renderer, debug); 
+        let row_height = renderer.line_height(); 
+        let text_color = renderer.palette.text; 
+        let dim_color = renderer.palette.dim_text; 
+
+        write_text_left(format!("OPEN ROM  {}", browser.dir.display()), BROWSER_MARGIN, BROWSER_MARGIN, text_color, renderer, canvas); 
+
+        let list_y = BROWSER_MARGIN + row_height * BROWSER_HEADER_ROWS; 
+        let rows = browser_rows(row_height, debug); 
+        for (row, entry) in browser.entries.iter().skip(browser.scroll).take(rows).enumerate() { 
+            let idx = browser.scroll + row; 
+            let y_off = list_y + row_height * row as i32; 
+            let color = if idx == browser.selected { 
+                canvas.set_draw_color(renderer.palette.grid); 
+                canvas.fill_rect(Rect::new(BROWSER_MARGIN / 2, y_off, logical_width - BROWSER_MARGIN as u32, row_height as u32)).unwrap(); 
+                renderer.palette.pc_highlight
+            } else { 
+                text_color
+            }; 
+            write_text_left(entry.label(), BROWSER_MARGIN, y_off, color, renderer, canvas); 
+        }
+        if browser.entries.is_empty() { 
+            write_text_left("(no roms here)".to_string(), BROWSER_MARGIN, list_y, dim_color, renderer, canvas); 
+        }
+
+        let footer_y = logical_height as i32 - BROWSER_MARGIN - row_height * BROWSER_FOOTER_ROWS; 
+        match browser.error.as_ref() { 
+            Some(error) => write_text_left(error.clone(), BROWSER_MARGIN, footer_y, renderer.palette.changed, renderer, canvas), 
+            None => write_text_left("* recent".to_string(), BROWSER_MARGIN, footer_y, dim_color, renderer, canvas), 
+        }
+        write_text_left("[ENTER] OPEN  [BACKSPACE] UP  [ESC] QUIT".to_string(), BROWSER_MARGIN, footer_y + row_height, dim_color, renderer, canvas); 
+
+        canvas.present(); 
+    }
+
+    // clear the window and set up drawing in logical canvas coordinates, scaled into the viewport
+    fn begin_frame(canvas: &mut Canvas<Window>, renderer: &mut Renderer, debug: bool) -> (u32, u32) { 
+        canvas.set_draw_color(LETTERBOX_COLOR); 
+        canvas.clear();
+
+        let viewport = renderer.viewport(canvas, debug); 
+        let (logical_width, logical_height) = logical_size(debug); 
+        canvas.set_scale(viewport.scale, viewport.scale).expect("ERROR:: failed to set canvas scale"); 
+        canvas.set_viewport(Rect::new(
+            (viewport.x as f32 / viewport.scale) as i32, 
+            (viewport.y as f32 / viewport.scale) as i32, 
+            logical_width, 
+            logical_height
+        )); 
+        canvas.set_draw_color(renderer.palette.panel); 
+        canvas.fill_rect(Rect::new(0, 0, logical_width, logical_height)).unwrap(); 
+        (logical_width, logical_height)
+    }
+
+    pub fn draw_entire_window(canvas: &mut Canvas<Window>, renderer: &mut Renderer, cpu: &CPU, debug_state: &DebugState, debug: bool, paused_state: bool) { 
+    begin_frame(canvas, renderer, debug); 
 
     if debug { 
-        draw_register_region(cpu, canvas, font); 
-        draw_pc_region(cpu, canvas, font); 
-        draw_i_region(cpu, canvas, font); 
+        draw_register_region(cpu, debug_state, canvas, renderer); 
+        draw_pc_region(cpu, debug_state, canvas, renderer); 
+        match debug_state.panel { 
+            DebugPanel::IRegister => draw_i_region(cpu, canvas, renderer), 
+            DebugPanel::Sprites => draw_sprite_region(cpu, debug_state, canvas, renderer), 
+            DebugPanel::Memory => draw_memory_region(cpu, debug_state, canvas, renderer), 
+            DebugPanel::CallStack => draw_call_stack_region(cpu, debug_state, canvas, renderer), 
+        }
     }
 
-    draw_rom_region(canvas, &cpu.pixels); 
+    draw_rom_region(canvas, renderer); 
+
+    if renderer.keypad.visible { 
+        draw_keypad(cpu, canvas, renderer); 
+    }
 
     if debug { 
-        canvas.set_draw_color(Color::WHITE); 
+        canvas.set_draw_color(renderer.palette.grid); 
         canvas.draw_line(Point::new(REGION_WIDTH, 0), Point::new(REGION_WIDTH, REGION_HEIGHT*2)).unwrap();
         canvas.draw_line(Point::new(0, REGION_HEIGHT), Point::new(REGION_WIDTH*2, REGION_HEIGHT)).unwrap(); 
     }
@@ -292,11 +1094,157 @@ pub mod video {
         write_text("PRESS [SPACE] TO START ROM".to_string(),
             REGIONS[ROM_REGION].0 + REGION_WIDTH / 2, 
             REGIONS[ROM_REGION].1 + REGION_HEIGHT / 2, 
-            Color::GRAY, 
-            font, 
+            renderer.palette.dim_text, 
+            renderer, 
             canvas)
     }
 
     canvas.present(); 
 }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests { 
+    use super::video::{browser_entry_at, browser_rows, compute_viewport, disasm_addr_at, edit_target_at, i_region_words, keypad_key_at, frame_to_rgb, DisplayFilter, Palette, Persistence, ScaleMode, CANVAS_WIDTH, CANVAS_HEIGHT}; 
+    use crate::browser::browser::{BrowserEntry, RomBrowser}; 
+    use crate::cpu::cpu::CPU; 
+    use crate::debugger::debugger::{DebugPanel, DebugState, EditTarget}; 
+    use std::path::{Path, PathBuf}; 
+    use sdl2::pixels::Color; 
+
+    #[test]
+    fn should_snap_to_whole_chip8_pixels_when_integer_scaling() { 
+        // 1000x700 fits 15.6 window pixels per CHIP-8 pixel, integer mode uses 15
+        let viewport = compute_viewport((1000, 700), false, ScaleMode::Integer); 
+
+        assert!(viewport.scale == 1.5); 
+        assert!(viewport.x == (1000 - 960) / 2); 
+        assert!(viewport.y == (700 - 480) / 2); 
+    }
+
+    #[test]
+    fn should_fill_limiting_dimension_when_fit_scaling() { 
+        let viewport = compute_viewport((1000, 700), false, ScaleMode::Fit); 
+
+        assert!(viewport.x == 0); 
+        assert!((CANVAS_WIDTH as f32 * viewport.scale) as u32 == 1000); 
+        assert!(viewport.y == (700 - (CANVAS_HEIGHT as f32 * viewport.scale) as i32) / 2); 
+    }
+
+    #[test]
+    fn should_map_window_coordinates_back_to_logical_canvas() { 
+        let viewport = compute_viewport((1000, 700), false, ScaleMode::Integer); 
+
+        assert!(viewport.to_logical(viewport.x, viewport.y) == (0, 0)); 
+        assert!(viewport.to_logical(viewport.x + 15, viewport.y + 30) == (10, 20)); 
+    }
+
+    #[test]
+    fn should_build_custom_palette_from_hex_pair() { 
+        let palette = Palette::from_name("#33FF66,#001a08").unwrap(); 
+
+        assert!(palette.pixel_on == Color::RGB(0x33, 0xFF, 0x66)); 
+        assert!(palette.pixel_off == Color::RGB(0x00, 0x1A, 0x08)); 
+        assert!(Palette::from_name("#33FF66").is_none()); 
+        assert!(Palette::from_name("sepia").is_none()); 
+        assert!(Palette::from_name("#+1+2+3,#000000").is_none()); 
+    }
+
+    #[test]
+    fn should_map_frame_buffer_through_palette() { 
+        let palette = Palette::from_name("amber").unwrap(); 
+
+        let rgb = frame_to_rgb(&[1.0, 0.0], &palette); 
+
+        assert!(rgb == vec![255, 176, 0, 26, 14, 0]); 
+    }
+
+    #[test]
+    fn should_keep_pixel_lit_for_one_extra_frame_when_or_last_two() { 
+        let mut filter = DisplayFilter::new(Persistence::OrLastTwo); 
+
+        filter.apply(&[true]); 
+        assert!(filter.apply(&[false])[0] == 1.0); 
+        assert!(filter.apply(&[false])[0] == 0.0); 
+    }
+
+    #[test]
+    fn should_fade_unlit_pixels_by_strength_when_decay() { 
+        let mut filter = DisplayFilter::new(Persistence::Decay(0.5)); 
+
+        filter.apply(&[true]); 
+        assert!(filter.apply(&[false])[0] == 0.5); 
+        assert!(filter.apply(&[false])[0] == 0.25); 
+        assert!(filter.apply(&[true])[0] == 1.0); 
+    }
+
+    #[test]
+    fn should_show_placeholder_for_bytes_past_end_of_memory_in_i_region() {
+        let mut cpu = CPU::new();
+        cpu.reg_i = cpu.memory.len() - 1;
+        cpu.memory[0xFFF] = 0xAB;
+
+        assert!(i_region_words(&cpu) == vec![(0xFFF, "ab--".to_string())]);
+
+        cpu.reg_i = 0x200;
+        assert!(i_region_words(&cpu).len() == 5);
+    }
+
+    #[test]
+    fn should_find_register_or_memory_byte_under_click() { 
+        let (line_height, char_width) = (20, 10); 
+        let mut state = DebugState::new(); 
+        state.memory_scroll = 0x40; 
+
+        // V0 sits in the first cell of the register grid, one double-height row down
+        assert!(edit_target_at(CANVAS_WIDTH as i32 + 128, 45, line_height, char_width, &state, 0x1000) == Some(EditTarget::Register(0))); 
+        assert!(edit_target_at(CANVAS_WIDTH as i32 + 256, 205, line_height, char_width, &state, 0x1000) == Some(EditTarget::Pc)); 
+
+        // memory bytes only respond while the memory panel is visible
+        let (memory_x, memory_y) = (CANVAS_WIDTH as i32 + 100, CANVAS_HEIGHT as i32 + 45); 
+        assert!(edit_target_at(memory_x + 30, memory_y, line_height, char_width, &state, 0x1000).is_none()); 
+        state.panel = DebugPanel::Memory; 
+        assert!(edit_target_at(memory_x + 30, memory_y, line_height, char_width, &state, 0x1000) == Some(EditTarget::Memory(0x209))); 
+        assert!(edit_target_at(memory_x + 25, memory_y, line_height, char_width, &state, 0x1000).is_none()); 
+    }
+
+    #[test]
+    fn should_find_disassembly_row_under_click() { 
+        let line_height = 20; 
+        let rows = (CANVAS_HEIGHT as i32 / line_height - 1) as usize; 
+        let middle_row_y = CANVAS_HEIGHT as i32 + line_height * (rows / 2 + 1) as i32 + 5; 
+
+        assert!(disasm_addr_at(100, middle_row_y, line_height, 0x2a4, 0x1000) == Some(0x2a4)); 
+        assert!(disasm_addr_at(100, middle_row_y + line_height, line_height, 0x2a4, 0x1000) == Some(0x2a6)); 
+        assert!(disasm_addr_at(100, CANVAS_HEIGHT as i32 + 5, line_height, 0x2a4, 0x1000).is_none()); 
+        assert!(disasm_addr_at(100, CANVAS_HEIGHT as i32 + line_height + 5, line_height, 0x0, 0x1000).is_none()); 
+    }
+
+    #[test]
+    fn should_find_keypad_key_under_click() { 
+        // the keypad sits in the bottom right corner of the display, 4 keys of 36 plus 3 gaps of 4
+        let (origin_x, origin_y) = (CANVAS_WIDTH as i32 - 8 - 156, CANVAS_HEIGHT as i32 - 8 - 156); 
+
+        assert!(keypad_key_at(origin_x + 1, origin_y + 1) == Some(0x1)); 
+        assert!(keypad_key_at(origin_x + 40 * 3 + 10, origin_y + 10) == Some(0xC)); 
+        assert!(keypad_key_at(origin_x + 40 + 10, origin_y + 40 * 3 + 10) == Some(0x0)); 
+        assert!(keypad_key_at(origin_x + 37, origin_y + 10).is_none()); 
+        assert!(keypad_key_at(origin_x - 1, origin_y + 10).is_none()); 
+    }
+
+    #[test] 
+    fn should_map_clicks_to_visible_browser_entries() { 
+        let line_height = 20; 
+        let mut browser = RomBrowser::new(Path::new("/"), &[]); 
+        browser.entries = (0..40).map(|idx| BrowserEntry::Rom(PathBuf::from(format!("{}.ch8", idx)))).collect(); 
+        browser.scroll = 3; 
+        let first_row_y = 12 + line_height * 2 + 5; 
+        let rows = browser_rows(line_height, false); 
+
+        assert!(browser_entry_at(50, first_row_y, line_height, false, &browser) == Some(3)); 
+        assert!(browser_entry_at(50, first_row_y + line_height * 2, line_height, false, &browser) == Some(5)); 
+        assert!(browser_entry_at(50, first_row_y + line_height * rows as i32, line_height, false, &browser).is_none()); 
+        assert!(browser_entry_at(50, 5, line_height, false, &browser).is_none()); 
+    }
+}